@@ -0,0 +1,125 @@
+//! Archive extraction for release assets (`.tar.gz` / `.tgz` and `.zip`)
+
+use anyhow::{Context, Result};
+use std::io::Read;
+
+use crate::error::TuxBoxError;
+
+/// True if the asset name looks like an archive we can unpack
+pub fn is_archive(name: &str) -> bool {
+    name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".zip")
+}
+
+/// Extract the files listed in `wanted` from an archive, in the same order.
+///
+/// Each entry is either a bare file name, matched anywhere in the archive
+/// (`rg`), or a relative path matched against the end of the entry path
+/// (`ripgrep-14.1.0-x86_64-unknown-linux-musl/rg`). Fails if any is missing.
+pub fn extract_files(archive_name: &str, data: &[u8], wanted: &[String]) -> Result<Vec<Vec<u8>>> {
+    let mut found: Vec<Option<Vec<u8>>> = vec![None; wanted.len()];
+
+    if archive_name.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(data))
+            .with_context(|| format!("Failed to open {}", archive_name))?;
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i)?;
+            if !file.is_file() {
+                continue;
+            }
+            let path = file.name().to_string();
+            if let Some(slot) = matching_slot(&path, wanted, &found) {
+                let mut buf = Vec::new();
+                file.read_to_end(&mut buf)?;
+                found[slot] = Some(buf);
+            }
+        }
+    } else {
+        let decoder = flate2::read::GzDecoder::new(data);
+        let mut archive = tar::Archive::new(decoder);
+        for entry in archive
+            .entries()
+            .with_context(|| format!("Failed to open {}", archive_name))?
+        {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.to_string_lossy().to_string();
+            if let Some(slot) = matching_slot(&path, wanted, &found) {
+                let mut buf = Vec::new();
+                entry.read_to_end(&mut buf)?;
+                found[slot] = Some(buf);
+            }
+        }
+    }
+
+    found
+        .into_iter()
+        .zip(wanted)
+        .map(|(data, name)| {
+            data.ok_or_else(|| {
+                TuxBoxError::ExecutionError(format!("'{}' not found in {}", name, archive_name))
+                    .into()
+            })
+        })
+        .collect()
+}
+
+fn matching_slot(path: &str, wanted: &[String], found: &[Option<Vec<u8>>]) -> Option<usize> {
+    let path = path.trim_start_matches("./");
+    wanted
+        .iter()
+        .enumerate()
+        .find(|(i, w)| found[*i].is_none() && entry_matches(path, w))
+        .map(|(i, _)| i)
+}
+
+fn entry_matches(path: &str, wanted: &str) -> bool {
+    let wanted = wanted.trim_start_matches("./");
+    if wanted.contains('/') {
+        path == wanted || path.ends_with(&format!("/{}", wanted))
+    } else {
+        path.rsplit('/').next() == Some(wanted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_matches() {
+        assert!(entry_matches("rg", "rg"));
+        assert!(entry_matches("ripgrep-14/rg", "rg"));
+        assert!(entry_matches("ripgrep-14/rg", "ripgrep-14/rg"));
+        assert!(!entry_matches("ripgrep-14/doc/rg.1", "rg"));
+        assert!(!entry_matches("other/rg", "ripgrep-14/rg"));
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (path, body) in [("pkg/tool", "bin"), ("pkg/tool-agent", "agent")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, body.as_bytes())
+                .unwrap();
+        }
+        let data = builder.into_inner().unwrap().finish().unwrap();
+
+        let files = extract_files(
+            "pkg.tar.gz",
+            &data,
+            &["tool-agent".to_string(), "pkg/tool".to_string()],
+        )
+        .unwrap();
+        assert_eq!(files, vec![b"agent".to_vec(), b"bin".to_vec()]);
+        assert!(extract_files("pkg.tar.gz", &data, &["missing".to_string()]).is_err());
+    }
+}
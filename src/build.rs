@@ -0,0 +1,238 @@
+//! Build-from-source tool types: `cargo`, `go` and `make`
+//!
+//! The repository is cloned like any other tool, built with the local
+//! toolchain and the resulting binary is executed directly (no Docker).
+//!
+//! - `cargo`: `cargo build --release --target-dir ~/.tuxbox/build/<tool>`
+//! - `go`:    `go build -o ~/.tuxbox/build/<tool>/ ./...`
+//! - `make`:  `make`, then the binary is copied into `~/.tuxbox/build/<tool>`
+//!
+//! `commands.setup` replaces the default build command (run in the repo, with
+//! `TUXBOX_BUILD_DIR` set). The first word of `commands.run` names the binary
+//! (default: the tool name); the remaining words are passed before user args.
+//!
+//! The built binary is cached by commit in `.tuxbox-state.toml`, so a run
+//! rebuilds only after HEAD moved (`tbox update`, `tbox rollback`).
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{ToolConfig, build_dir};
+use crate::error::TuxBoxError;
+use crate::git;
+use crate::tool_state::ToolState;
+
+/// Tool types handled by this module
+const BUILD_TYPES: &[&str] = &["cargo", "go", "make"];
+
+/// True for `cargo`, `go` and `make` tools
+pub fn is_build_type(tool_type: Option<&str>) -> bool {
+    tool_type.is_some_and(|t| BUILD_TYPES.contains(&t))
+}
+
+/// Run a build-from-source tool, building it first if HEAD changed since the last build
+pub fn run_built_tool(tool_config: &ToolConfig, tool_path: &Path, args: &[String]) -> Result<()> {
+    let method = tool_config.tool_type.as_deref().unwrap_or_default();
+    let (binary_name, leading_args) = run_parts(tool_config);
+    let head = git::head_commit(tool_path)?;
+
+    let cached = ToolState::load(tool_path)
+        .and_then(|s| s.build)
+        .filter(|b| b.commit == head)
+        // The build directory may have been cleaned since
+        .filter(|b| b.binary.is_file());
+
+    let binary = match cached {
+        Some(build) => {
+            println!(
+                "  {} Build up to date ({})",
+                "✓".green(),
+                short(&build.commit).dimmed()
+            );
+            build.binary
+        }
+        None => {
+            let binary = build_tool(tool_config, tool_path, method, &binary_name)?;
+            if let Err(e) = ToolState::for_build(method, head, binary.clone()).save(tool_path) {
+                // Non-fatal: a failed save just means next run will rebuild
+                eprintln!("  {} Could not save tool state: {}", "⚠".yellow(), e);
+            }
+            binary
+        }
+    };
+
+    let mut cmd = Command::new(&binary);
+    cmd.args(&leading_args).args(args);
+    let binary_dir = binary.parent().unwrap_or(tool_path);
+    let status = crate::sandbox::wrap(tool_config, cmd, &[binary_dir, tool_path])?
+        .status()
+        .map_err(|e| TuxBoxError::ExecutionError(format!("Failed to execute binary: {}", e)))?;
+
+    if !status.success() {
+        return Err(TuxBoxError::ExecutionError(format!(
+            "Binary exited with code: {}",
+            status.code().unwrap_or(-1)
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Binary name and leading arguments from `commands.run`
+fn run_parts(tool_config: &ToolConfig) -> (String, Vec<String>) {
+    let mut parts: Vec<String> = tool_config
+        .commands
+        .as_ref()
+        .map(|c| c.run.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    if parts.is_empty() {
+        (tool_config.name.clone(), parts)
+    } else {
+        let binary = parts.remove(0);
+        (binary, parts)
+    }
+}
+
+/// Build the tool and return the path of the cached binary
+fn build_tool(
+    tool_config: &ToolConfig,
+    tool_path: &Path,
+    method: &str,
+    binary_name: &str,
+) -> Result<PathBuf> {
+    let out_dir = build_dir()?.join(&tool_config.name);
+    fs::create_dir_all(&out_dir).context("Failed to create build directory")?;
+
+    let custom = tool_config
+        .commands
+        .as_ref()
+        .and_then(|c| c.setup.as_deref());
+    let command: Vec<String> = match (custom, method) {
+        (Some(setup), _) => setup.split_whitespace().map(str::to_string).collect(),
+        (None, "cargo") => vec![
+            "cargo".into(),
+            "build".into(),
+            "--release".into(),
+            "--target-dir".into(),
+            out_dir.to_string_lossy().to_string(),
+        ],
+        (None, "go") => vec![
+            "go".into(),
+            "build".into(),
+            "-o".into(),
+            format!("{}/", out_dir.display()),
+            "./...".into(),
+        ],
+        (None, _) => vec!["make".into()],
+    };
+    if command.is_empty() {
+        return Err(TuxBoxError::ConfigError("Empty build command".into()).into());
+    }
+
+    println!(
+        "  {} Building {} ({})...",
+        "→".cyan(),
+        tool_config.name.bold(),
+        command.join(" ").dimmed()
+    );
+
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(tool_path)
+        .env("TUXBOX_BUILD_DIR", &out_dir)
+        .status()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                TuxBoxError::ExecutionError(format!(
+                    "'{}' not found. {}",
+                    command[0],
+                    toolchain_hint(method)
+                ))
+            } else {
+                TuxBoxError::ExecutionError(format!("Failed to run {}: {}", command[0], e))
+            }
+        })?;
+
+    if !status.success() {
+        return Err(TuxBoxError::ExecutionError(format!(
+            "Build of '{}' failed with code: {}",
+            tool_config.name,
+            status.code().unwrap_or(-1)
+        ))
+        .into());
+    }
+
+    let search_dirs = [
+        out_dir.join("release"),
+        out_dir.clone(),
+        tool_path.join("bin"),
+        tool_path.join("build"),
+        tool_path.to_path_buf(),
+    ];
+    let built = search_dirs
+        .iter()
+        .map(|dir| dir.join(binary_name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            TuxBoxError::ExecutionError(format!(
+                "Build succeeded but no '{}' binary was found in {}. \
+                 Set commands.run to the binary name",
+                binary_name,
+                out_dir.display()
+            ))
+        })?;
+
+    // Keep binaries built inside the repo (make, custom commands) out of the
+    // work tree, so the cache survives `make clean` and a later checkout
+    let binary = if built.starts_with(&out_dir) {
+        built
+    } else {
+        let target = out_dir.join(binary_name);
+        fs::copy(&built, &target).context("Failed to copy built binary")?;
+        target
+    };
+
+    println!("  {} Built {}", "✓".green(), binary.display());
+    Ok(binary)
+}
+
+fn toolchain_hint(method: &str) -> &'static str {
+    match method {
+        "cargo" => "Install the Rust toolchain (https://rustup.rs) to build this tool",
+        "go" => "Install Go (https://go.dev/dl) to build this tool",
+        _ => "Install make and the tool's build dependencies",
+    }
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Commands;
+
+    #[test]
+    fn test_run_parts() {
+        let mut config = ToolConfig {
+            name: "foo".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(run_parts(&config), ("foo".to_string(), vec![]));
+
+        config.commands = Some(Commands {
+            setup: None,
+            run: "foo-cli --quiet".to_string(),
+        });
+        assert_eq!(
+            run_parts(&config),
+            ("foo-cli".to_string(), vec!["--quiet".to_string()])
+        );
+    }
+}
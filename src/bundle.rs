@@ -0,0 +1,572 @@
+//! Offline bundles for air-gapped hosts
+//!
+//! `tbox bundle python <tool>` downloads every wheel a Python tool installs into
+//! `~/.tuxbox/wheelhouse/<tool>`. Copied to the same path on a host without
+//! internet access, the venv install then runs with `--no-index --find-links`.
+//!
+//! `tbox bundle export <tools...> -o bundle.tar.gz` packs everything a set of
+//! installed tools needs into one archive described by `manifest.toml`:
+//!
+//! ```text
+//! manifest.toml            registries, tools and the exporting TuxBox home
+//! registries/<name>.bundle git bundle of each configured registry
+//! signing/<name>.allowed_signers  SSH signers of a signed registry
+//! tools/<tool>.bundle      git bundle of each git tool checkout
+//! state/<tool>.toml        the tool's .tuxbox-state.toml
+//! native/<tool>/           installed native tool directory
+//! wheelhouse/<tool>/       Python wheelhouse (from `tbox bundle python`)
+//! images/<tool>.tar        saved container image of the tool (--images)
+//! ```
+//!
+//! `tbox bundle import` mirrors the git bundles under `~/.tuxbox/mirrors`,
+//! points the registries at those mirrors (`file://` URLs), carrying over their
+//! signing requirements, and restores the tools, so `tbox run` and
+//! `tbox update` work without network access.
+//!
+//! libgit2 cannot read or write git bundles, so export and import need the
+//! `git` command; everything else goes through git2.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{self, RegistrySigning, registry_dir, tools_dir, tuxbox_home};
+use crate::error::TuxBoxError;
+use crate::tool_state::ToolState;
+use crate::{credentials, docker, environment, git, native, python, registry, runner, trust};
+
+const MANIFEST_FILE: &str = "manifest.toml";
+const MANIFEST_VERSION: u32 = 1;
+
+/// Build the wheelhouse for a Python tool
+pub fn bundle_python(tool_name: &str) -> Result<()> {
+    // `pip download` builds sdists, which runs the packages' setup code:
+    // registry tools need the same approval as `tbox run`
+    let (tool_config, registry) = runner::resolve_tool(tool_name)?;
+    if let Some(registry) = &registry {
+        trust::confirm_tool(&tool_config, registry)?;
+    }
+
+    if tool_config.tool_type.as_deref() != Some("python") {
+        return Err(TuxBoxError::ConfigError(format!(
+            "'{}' is not a Python tool (type: {})",
+            tool_name,
+            tool_config.tool_type.as_deref().unwrap_or("unknown")
+        ))
+        .into());
+    }
+
+    if !git::is_tool_cloned(tool_name)? {
+        println!("  Tool not installed, cloning...");
+        git::clone_tool(
+            tool_name,
+            &tool_config.repo,
+            tool_config.branch.as_deref(),
+            tool_config.signing.as_ref(),
+        )?;
+    }
+    let tool_path = git::tool_path(tool_name)?;
+    git::verify_checkout(tool_name, &tool_path, tool_config.signing.as_ref())?;
+
+    let wheelhouse = python::wheelhouse_path(tool_name)?;
+    let count = python::download_wheels(&tool_config, &tool_path, &wheelhouse)?;
+
+    println!(
+        "  {} {} packages in {}",
+        "✓".green(),
+        count,
+        wheelhouse.display()
+    );
+    println!(
+        "  {} Copy this directory to the same path on the offline host; installs there use it before any index",
+        "→".dimmed()
+    );
+    Ok(())
+}
+
+/// Table of contents of a bundle archive
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// TuxBox version that wrote the bundle
+    tuxbox_version: String,
+    /// TuxBox home of the exporting host; state paths are rebased from it
+    tuxbox_home: PathBuf,
+    #[serde(default)]
+    registries: Vec<BundledRegistry>,
+    #[serde(default)]
+    tools: Vec<BundledTool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundledRegistry {
+    name: String,
+    /// Original URL, kept for reference
+    url: String,
+    priority: u32,
+    /// Signing requirements; `allowed_signers` points into the archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signing: Option<RegistrySigning>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundledTool {
+    name: String,
+    kind: BundledKind,
+    /// Checked-out commit (git tools)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    #[serde(default)]
+    wheelhouse: bool,
+    #[serde(default)]
+    image: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BundledKind {
+    Git,
+    Native,
+}
+
+/// Scratch directory under the TuxBox home, removed when dropped.
+/// Kept on the same filesystem so imports can move files into place.
+struct Staging(PathBuf);
+
+impl Staging {
+    fn new(purpose: &str) -> Result<Self> {
+        let dir = tuxbox_home()?.join(format!(".{}-{}", purpose, std::process::id()));
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir).context("Failed to create staging directory")?;
+        Ok(Staging(dir))
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Pack registries and the given tools (all installed tools when empty) into `output`
+pub fn export(tools: &[String], output: &Path, images: bool) -> Result<()> {
+    let config = config::load_config()?;
+    let home = tuxbox_home()?;
+    let staging = Staging::new("bundle-export")?;
+
+    let runtime = if images {
+        Some(environment::detect_container_runtime().ok_or_else(|| {
+            TuxBoxError::ExecutionError(
+                "--images needs a container runtime (docker, podman or nerdctl)".into(),
+            )
+        })?)
+    } else {
+        None
+    };
+
+    let tool_names = if tools.is_empty() {
+        installed_tools()?
+    } else {
+        tools.to_vec()
+    };
+    if tool_names.is_empty() {
+        return Err(TuxBoxError::ConfigError("No installed tools to bundle".into()).into());
+    }
+
+    let file =
+        fs::File::create(output).with_context(|| format!("Cannot create {}", output.display()))?;
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
+        file,
+        flate2::Compression::default(),
+    ));
+
+    let mut manifest = Manifest {
+        version: MANIFEST_VERSION,
+        tuxbox_version: env!("CARGO_PKG_VERSION").to_string(),
+        tuxbox_home: home,
+        registries: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    for registry_config in &config.registries {
+        let checkout = registry_dir()?.join(&registry_config.name);
+        if !checkout.exists() {
+            eprintln!(
+                "  {} Registry '{}' not synced, skipping (run 'tbox registry sync')",
+                "⚠".yellow(),
+                registry_config.name
+            );
+            continue;
+        }
+        let bundle = staging.0.join(format!("{}.bundle", registry_config.name));
+        create_git_bundle(&checkout, &bundle)?;
+        archive.append_path_with_name(
+            &bundle,
+            format!("registries/{}.bundle", registry_config.name),
+        )?;
+        let mut signing = registry_config.signing.clone();
+        if let Some(signers) = signing.as_mut().and_then(|s| s.allowed_signers.as_mut()) {
+            let cwd = std::env::current_dir()?;
+            let source = config::expand_path(signers, &dirs::home_dir().unwrap_or_default(), &cwd);
+            let name = format!("signing/{}.allowed_signers", registry_config.name);
+            archive
+                .append_path_with_name(&source, &name)
+                .with_context(|| format!("Cannot bundle {}", source.display()))?;
+            *signers = name;
+        }
+        println!("  {} Registry {}", "✓".green(), registry_config.name.bold());
+        manifest.registries.push(BundledRegistry {
+            name: registry_config.name.clone(),
+            url: registry_config.url.clone(),
+            priority: registry_config.priority,
+            signing,
+        });
+    }
+
+    for name in &tool_names {
+        let tool_path = tools_dir()?.join(name);
+        let mut entry = if native::native_tool_dir(name)?.is_some() {
+            archive.append_dir_all(format!("native/{}", name), &tool_path)?;
+            BundledTool {
+                name: name.clone(),
+                kind: BundledKind::Native,
+                commit: None,
+                wheelhouse: false,
+                image: false,
+            }
+        } else if git::is_tool_cloned(name)? {
+            let bundle = staging.0.join(format!("{}.bundle", name));
+            create_git_bundle(&tool_path, &bundle)?;
+            archive.append_path_with_name(&bundle, format!("tools/{}.bundle", name))?;
+            let state_file = tool_path.join(".tuxbox-state.toml");
+            if state_file.exists() {
+                archive.append_path_with_name(&state_file, format!("state/{}.toml", name))?;
+            }
+            BundledTool {
+                name: name.clone(),
+                kind: BundledKind::Git,
+                commit: Some(git::head_commit(&tool_path)?),
+                wheelhouse: false,
+                image: false,
+            }
+        } else {
+            return Err(TuxBoxError::ConfigError(format!(
+                "'{}' is not installed (run it once before bundling)",
+                name
+            ))
+            .into());
+        };
+
+        let wheelhouse = python::wheelhouse_path(name)?;
+        if wheelhouse.is_dir() {
+            archive.append_dir_all(format!("wheelhouse/{}", name), &wheelhouse)?;
+            entry.wheelhouse = true;
+        }
+
+        if let Some(runtime) = runtime {
+            let image = staging.0.join(format!("{}.tar", name));
+            if docker::save_image(runtime, name, &image)? {
+                archive.append_path_with_name(&image, format!("images/{}.tar", name))?;
+                fs::remove_file(&image)?;
+                entry.image = true;
+            }
+        }
+
+        println!("  {} Tool {}", "✓".green(), name.bold());
+        manifest.tools.push(entry);
+    }
+
+    let manifest_toml = toml::to_string_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_toml.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, MANIFEST_FILE, manifest_toml.as_bytes())?;
+    archive.into_inner()?.finish()?;
+
+    println!(
+        "  {} {} registries, {} tools → {}",
+        "✓".green(),
+        manifest.registries.len(),
+        manifest.tools.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Restore a bundle written by [`export`]
+pub fn import(archive_path: &Path) -> Result<()> {
+    let staging = Staging::new("bundle-import")?;
+    let file = fs::File::open(archive_path)
+        .with_context(|| format!("Cannot open {}", archive_path.display()))?;
+    tar::Archive::new(flate2::read::GzDecoder::new(file))
+        .unpack(&staging.0)
+        .context("Failed to unpack bundle")?;
+
+    let manifest: Manifest = toml::from_str(
+        &fs::read_to_string(staging.0.join(MANIFEST_FILE))
+            .context("Bundle has no manifest.toml")?,
+    )
+    .context("Invalid bundle manifest")?;
+    if manifest.version != MANIFEST_VERSION {
+        return Err(TuxBoxError::ConfigError(format!(
+            "Unsupported bundle version {} (written by tbox {})",
+            manifest.version, manifest.tuxbox_version
+        ))
+        .into());
+    }
+    // Names become file names below the TuxBox home
+    for bundled in &manifest.registries {
+        config::check_name("registry", &bundled.name)?;
+    }
+    for bundled in &manifest.tools {
+        config::check_name("tool", &bundled.name)?;
+    }
+
+    let home = tuxbox_home()?;
+    let mirrors = home.join("mirrors");
+    let runtime = manifest
+        .tools
+        .iter()
+        .any(|tool| tool.image)
+        .then(environment::detect_container_runtime)
+        .flatten();
+
+    for bundled in &manifest.registries {
+        let bundle = staging
+            .0
+            .join(format!("registries/{}.bundle", bundled.name));
+        let mirror = mirrors
+            .join("registries")
+            .join(format!("{}.git", bundled.name));
+        mirror_git_bundle(&bundle, &mirror)?;
+
+        // Signers are kept next to the mirror, outside the staging directory
+        let mut signing = bundled.signing.clone();
+        if let Some(signers) = signing.as_mut().and_then(|s| s.allowed_signers.as_mut()) {
+            let dest = mirrors
+                .join("registries")
+                .join(format!("{}.allowed_signers", bundled.name));
+            fs::copy(
+                staging
+                    .0
+                    .join(format!("signing/{}.allowed_signers", bundled.name)),
+                &dest,
+            )
+            .context("Bundle lacks the registry's allowed_signers file")?;
+            *signers = dest.to_string_lossy().to_string();
+        }
+
+        let url = format!("file://{}", mirror.display());
+        let registry_config =
+            config::set_registry_url(&bundled.name, &url, bundled.priority, signing)?;
+        // The cached checkout still tracks the old URL: clone it again from the mirror
+        let checkout = registry_dir()?.join(&bundled.name);
+        if checkout.exists() {
+            fs::remove_dir_all(&checkout)?;
+        }
+        registry::sync_registry(&registry_config, &registry_dir()?)?;
+        println!(
+            "  {} Registry {} → {}",
+            "✓".green(),
+            bundled.name.bold(),
+            url.dimmed()
+        );
+    }
+
+    fs::create_dir_all(tools_dir()?)?;
+    for bundled in &manifest.tools {
+        let tool_path = tools_dir()?.join(&bundled.name);
+        if tool_path.exists() {
+            eprintln!(
+                "  {} {} is already installed, keeping the existing copy",
+                "⚠".yellow(),
+                bundled.name
+            );
+        } else {
+            match bundled.kind {
+                BundledKind::Git => {
+                    import_git_tool(bundled, &staging.0, &mirrors, &tool_path)?;
+                }
+                BundledKind::Native => {
+                    fs::rename(staging.0.join("native").join(&bundled.name), &tool_path)
+                        .context("Failed to restore native tool")?;
+                    if let Ok(tool_config) = runner::get_tool_config(&bundled.name)
+                        && let Err(e) = native::write_shims(&tool_config)
+                    {
+                        eprintln!("  {} Could not write launchers: {}", "⚠".yellow(), e);
+                    }
+                }
+            }
+            ToolState::rebase(&tool_path, &manifest.tuxbox_home, &home)?;
+        }
+
+        if bundled.wheelhouse {
+            let wheelhouse = python::wheelhouse_path(&bundled.name)?;
+            if wheelhouse.exists() {
+                fs::remove_dir_all(&wheelhouse)?;
+            }
+            fs::create_dir_all(wheelhouse.parent().unwrap_or(&home))?;
+            fs::rename(
+                staging.0.join("wheelhouse").join(&bundled.name),
+                &wheelhouse,
+            )
+            .context("Failed to restore wheelhouse")?;
+        }
+
+        if bundled.image {
+            match runtime {
+                Some(runtime) => docker::load_image(
+                    runtime,
+                    &staging.0.join(format!("images/{}.tar", bundled.name)),
+                )?,
+                None => eprintln!(
+                    "  {} No container runtime, skipping the image of {}",
+                    "⚠".yellow(),
+                    bundled.name
+                ),
+            }
+        }
+
+        println!("  {} Tool {}", "✓".green(), bundled.name.bold());
+    }
+
+    Ok(())
+}
+
+/// Restore a git tool from its bundle, tracking a local mirror as `origin`
+fn import_git_tool(
+    bundled: &BundledTool,
+    staging: &Path,
+    mirrors: &Path,
+    tool_path: &Path,
+) -> Result<()> {
+    let bundle = staging.join(format!("tools/{}.bundle", bundled.name));
+    let mirror = mirrors.join("tools").join(format!("{}.git", bundled.name));
+    mirror_git_bundle(&bundle, &mirror)?;
+
+    let url = mirror.to_string_lossy();
+    let repo = git2::Repository::clone(&url, tool_path)
+        .map_err(|e| credentials::git_error("clone", &url, e))?;
+    if let Some(commit) = &bundled.commit {
+        repo.revparse_single(commit)
+            .and_then(|target| repo.reset(&target, git2::ResetType::Hard, None))
+            .map_err(|e| {
+                TuxBoxError::GitError(format!(
+                    "Cannot check out {} from the bundle: {}",
+                    commit, e
+                ))
+            })?;
+    }
+
+    let state = staging.join(format!("state/{}.toml", bundled.name));
+    if state.exists() {
+        fs::copy(&state, tool_path.join(".tuxbox-state.toml"))?;
+    }
+    Ok(())
+}
+
+/// Names of all tools under `~/.tuxbox/tools`
+fn installed_tools() -> Result<Vec<String>> {
+    let dir = tools_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// `git bundle create` with every ref of the repository
+fn create_git_bundle(repo: &Path, dest: &Path) -> Result<()> {
+    run_git(
+        Some(repo),
+        &["bundle", "create", "-q", &dest.to_string_lossy(), "--all"],
+    )
+}
+
+/// Create (or refresh) a bare mirror from a git bundle
+fn mirror_git_bundle(bundle: &Path, mirror: &Path) -> Result<()> {
+    if mirror.exists() {
+        run_git(
+            Some(mirror),
+            &["fetch", "-q", &bundle.to_string_lossy(), "+refs/*:refs/*"],
+        )
+    } else {
+        fs::create_dir_all(mirror.parent().unwrap_or(mirror))?;
+        run_git(
+            None,
+            &[
+                "clone",
+                "-q",
+                "--mirror",
+                &bundle.to_string_lossy(),
+                &mirror.to_string_lossy(),
+            ],
+        )?;
+        // The bundle is only staged: do not keep it as the mirror's remote
+        run_git(Some(mirror), &["remote", "remove", "origin"])
+    }
+}
+
+fn run_git(dir: Option<&Path>, args: &[&str]) -> Result<()> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let output = cmd.args(args).output().map_err(|e| {
+        TuxBoxError::GitError(format!(
+            "Failed to execute git (needed for bundle export/import): {}",
+            e
+        ))
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(TuxBoxError::GitError(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            stderr.trim()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            tuxbox_version: "0.0.0".into(),
+            tuxbox_home: PathBuf::from("/home/alice/.tuxbox"),
+            registries: vec![BundledRegistry {
+                name: "main".into(),
+                url: "git@example.com:tools/registry.git".into(),
+                priority: 100,
+                signing: None,
+            }],
+            tools: vec![BundledTool {
+                name: "hfs".into(),
+                kind: BundledKind::Native,
+                commit: None,
+                wheelhouse: false,
+                image: false,
+            }],
+        };
+        let parsed: Manifest = toml::from_str(&toml::to_string_pretty(&manifest).unwrap()).unwrap();
+        assert_eq!(parsed.registries[0].name, "main");
+        assert_eq!(parsed.tools[0].kind, BundledKind::Native);
+    }
+}
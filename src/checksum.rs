@@ -0,0 +1,122 @@
+//! SHA-256 and minisign verification for downloaded release assets
+//!
+//! Releases usually publish either a per-asset `<asset>.sha256` file or a
+//! combined `SHA256SUMS`-style listing. Both use the `sha256sum` output format:
+//! `<hex digest>  <file name>` (binary-mode entries prefix the name with `*`).
+//! Signed releases additionally ship a detached `<asset>.minisig`.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+use crate::error::TuxBoxError;
+
+/// Combined checksum listings recognised in a release, in lookup order
+pub const SUMS_FILE_NAMES: &[&str] = &[
+    "SHA256SUMS",
+    "SHA256SUMS.txt",
+    "sha256sums.txt",
+    "checksums.txt",
+];
+
+/// Hex-encoded SHA-256 digest of `data`
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Extract the digest for `asset_name` from a `.sha256` file or a SHA256SUMS listing.
+///
+/// A single-line file containing only a digest (no file name) is accepted as-is.
+pub fn expected_digest(content: &str, asset_name: &str) -> Option<String> {
+    let lines = content.lines().map(str::trim).filter(|l| !l.is_empty());
+    let mut single: Option<String> = None;
+
+    for (i, line) in lines.enumerate() {
+        let mut parts = line.split_whitespace();
+        let digest = parts.next()?;
+        if !is_sha256_hex(digest) {
+            continue;
+        }
+        match parts.next() {
+            Some(name) if name.trim_start_matches('*') == asset_name => {
+                return Some(digest.to_lowercase());
+            }
+            None if i == 0 => single = Some(digest.to_lowercase()),
+            _ => {}
+        }
+    }
+
+    single
+}
+
+/// Fail with a `ChecksumMismatch` unless `data` hashes to `expected`
+pub fn verify(data: &[u8], expected: &str, asset_name: &str) -> Result<()> {
+    let actual = sha256_hex(data);
+    if actual != expected.trim().to_lowercase() {
+        return Err(TuxBoxError::ChecksumMismatch(
+            asset_name.to_string(),
+            expected.trim().to_lowercase(),
+            actual,
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Verify a detached minisign signature over `data` with a base64 public key
+pub fn verify_minisign(
+    data: &[u8],
+    signature: &str,
+    public_key: &str,
+    asset_name: &str,
+) -> Result<()> {
+    let public_key = minisign_verify::PublicKey::from_base64(public_key.trim())
+        .map_err(|e| TuxBoxError::SignatureError(format!("Invalid public key: {}", e)))?;
+    let signature = minisign_verify::Signature::decode(signature).map_err(|e| {
+        TuxBoxError::SignatureError(format!("Invalid signature for {}: {}", asset_name, e))
+    })?;
+    public_key.verify(data, &signature, false).map_err(|e| {
+        TuxBoxError::SignatureError(format!("Bad signature for {}: {}", asset_name, e))
+    })?;
+    Ok(())
+}
+
+fn is_sha256_hex(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(sha256_hex(b""), EMPTY);
+    }
+
+    #[test]
+    fn test_expected_digest() {
+        let sums = format!(
+            "{}  tool-linux-x86_64\n{} *tool-macos-arm64\n",
+            EMPTY,
+            "a".repeat(64)
+        );
+        assert_eq!(
+            expected_digest(&sums, "tool-linux-x86_64").as_deref(),
+            Some(EMPTY)
+        );
+        assert_eq!(
+            expected_digest(&sums, "tool-macos-arm64"),
+            Some("a".repeat(64))
+        );
+        assert_eq!(expected_digest(&sums, "tool-other"), None);
+        assert_eq!(expected_digest(EMPTY, "anything").as_deref(), Some(EMPTY));
+    }
+
+    #[test]
+    fn test_verify_mismatch() {
+        assert!(verify(b"", EMPTY, "x").is_ok());
+        assert!(verify(b"tampered", EMPTY, "x").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 //! Command-line interface definitions using Clap derive API (2026 pattern)
 
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "tbox")]
@@ -24,7 +25,7 @@ pub enum Commands {
 
     /// Run a tool (clones automatically if needed)
     Run {
-        /// Name of the tool to run
+        /// Name of the tool to run (`tool:entry` for a native entry point)
         tool: String,
 
         /// Arguments to pass to the tool
@@ -38,26 +39,120 @@ pub enum Commands {
         tool: Option<String>,
     },
 
+    /// Roll a tool back to the revision installed before its last update
+    Rollback {
+        /// Name of the tool to roll back
+        tool: String,
+    },
+
+    /// Rebuild a tool's Docker image (also happens automatically when the tool changes)
+    Rebuild {
+        /// Name of the tool to rebuild
+        tool: String,
+
+        /// Build without Docker's layer cache and pull fresh base images
+        #[arg(long)]
+        no_cache: bool,
+    },
+
     /// Show TuxBox status and installed tools
     Status,
 
+    /// List running tool containers
+    Ps,
+
+    /// Stop all running containers of a tool
+    Kill {
+        /// Name of the tool
+        tool: String,
+    },
+
+    /// Show a tool's definition and effective container permissions
+    Info {
+        /// Name of the tool
+        tool: String,
+    },
+
     /// Manage registries (add, remove, list, sync)
     Registry {
         #[command(subcommand)]
         action: RegistryAction,
     },
 
+    /// Manage trusted registries and approved tools
+    Trust {
+        #[command(subcommand)]
+        action: TrustAction,
+    },
+
+    /// Prepare offline installs for air-gapped hosts
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+
     /// Check for updates and optionally install them
     SelfUpdate {
         /// Automatically install the update without prompting
         #[arg(short, long)]
         install: bool,
+
+        /// Restore the binary replaced by the last self-update
+        #[arg(long, conflicts_with = "install")]
+        rollback: bool,
     },
 
     /// Show version information
     Version,
 }
 
+#[derive(Subcommand)]
+pub enum TrustAction {
+    /// List trusted registries, approved tools and the admin policy
+    List,
+
+    /// Run all tools of a registry without asking for confirmation
+    Registry {
+        /// Name of the registry
+        name: String,
+    },
+
+    /// Forget a trusted registry or a tool approval
+    Revoke {
+        /// Name of the registry or tool
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BundleAction {
+    /// Download all wheels of a Python tool into ~/.tuxbox/wheelhouse/<tool>
+    Python {
+        /// Name of the tool to bundle
+        tool: String,
+    },
+
+    /// Pack registries and installed tools into one archive for offline hosts
+    Export {
+        /// Tools to include (default: all installed tools)
+        tools: Vec<String>,
+
+        /// Archive to write (e.g. bundle.tar.gz)
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Also include Docker images (`docker save`)
+        #[arg(long)]
+        images: bool,
+    },
+
+    /// Restore an archive written by `tbox bundle export`
+    Import {
+        /// Archive to restore
+        archive: PathBuf,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum RegistryAction {
     /// List all configured registries
@@ -104,6 +199,46 @@ pub enum RegistryAction {
         priority: u32,
     },
 
+    /// Require signed revisions of a registry (commits and/or tools.toml)
+    SetSigning {
+        /// Name of the registry
+        name: String,
+
+        /// SSH allowed_signers file; every registry commit must be signed by one of its keys
+        #[arg(long)]
+        allowed_signers: Option<String>,
+
+        /// Minisign public key (base64) for tools.toml.minisig (repeatable)
+        #[arg(long = "minisign-key")]
+        minisign_keys: Vec<String>,
+
+        /// Stop requiring signatures
+        #[arg(long, conflicts_with_all = ["allowed_signers", "minisign_keys"])]
+        off: bool,
+    },
+
+    /// Set the credentials used to clone and update a registry
+    SetAuth {
+        /// Name of the registry
+        name: String,
+
+        /// SSH private key, offered right after the ssh-agent
+        #[arg(long)]
+        ssh_key: Option<String>,
+
+        /// Environment variable holding an HTTPS token
+        #[arg(long)]
+        token_env: Option<String>,
+
+        /// Username for SSH or for the HTTPS token
+        #[arg(long)]
+        username: Option<String>,
+
+        /// Go back to the default credentials
+        #[arg(long, conflicts_with_all = ["ssh_key", "token_env", "username"])]
+        off: bool,
+    },
+
     /// Sync (clone/update) all registries
     Sync,
 }
@@ -2,10 +2,19 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::credentials::GitConfig;
+use crate::docker::ContainerConfig;
 use crate::error::TuxBoxError;
+use crate::http::HttpConfig;
+use crate::native::NativeConfig;
+use crate::python::{PackageIndex, PythonConfig};
+use crate::release::ReleaseSource;
+use crate::sandbox::SandboxConfig;
+use crate::signing::ToolSigning;
 
 /// TuxBox home directory (~/.tuxbox)
 pub fn tuxbox_home() -> Result<PathBuf> {
@@ -19,6 +28,16 @@ pub fn tools_dir() -> Result<PathBuf> {
     Ok(tuxbox_home()?.join("tools"))
 }
 
+/// Build output of cargo/go/make tools (~/.tuxbox/build)
+pub fn build_dir() -> Result<PathBuf> {
+    Ok(tuxbox_home()?.join("build"))
+}
+
+/// Launcher shims for native tools and their entry points (~/.tuxbox/bin)
+pub fn shims_dir() -> Result<PathBuf> {
+    Ok(tuxbox_home()?.join("bin"))
+}
+
 /// Registry cache directory (~/.tuxbox/registry)
 pub fn registry_dir() -> Result<PathBuf> {
     Ok(tuxbox_home()?.join("registry"))
@@ -29,6 +48,23 @@ pub fn config_file() -> Result<PathBuf> {
     Ok(tuxbox_home()?.join("config.toml"))
 }
 
+/// Expand a leading `~`, `$HOME` or `$PWD` in a path from the registry or config
+pub fn expand_path(path: &str, home: &Path, cwd: &Path) -> PathBuf {
+    if path == "~" || path == "$HOME" {
+        home.to_path_buf()
+    } else if path == "$PWD" {
+        cwd.to_path_buf()
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        home.join(rest)
+    } else if let Some(rest) = path.strip_prefix("$HOME/") {
+        home.join(rest)
+    } else if let Some(rest) = path.strip_prefix("$PWD/") {
+        cwd.join(rest)
+    } else {
+        PathBuf::from(path)
+    }
+}
+
 /// TuxBox configuration with multi-registry support
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
@@ -38,6 +74,23 @@ pub struct Config {
     // Legacy support (Phase 1 compatibility)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub registry_url: Option<String>,
+
+    /// Release source for `tbox self-update` (default: GitHub disoardi/tuxbox)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_update: Option<ReleaseSource>,
+
+    /// HTTP settings for release downloads (per-host tokens)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpConfig>,
+    /// Python backend settings (uv or pip)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub python: Option<PythonConfig>,
+    /// Per-tool container permissions that widen the registry's (`[containers.<tool>]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub containers: Option<HashMap<String, ContainerConfig>>,
+    /// SSH keys for git remotes (registries and tool repos)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitConfig>,
 }
 
 /// Individual registry configuration
@@ -49,6 +102,50 @@ pub struct RegistryConfig {
     pub priority: u32,
     #[serde(default)]
     pub auth_type: AuthType,
+    /// Keys the registry must be signed with; unsigned revisions are refused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<RegistrySigning>,
+    /// Credentials for this registry's remote (beyond ssh-agent and [http.tokens])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<RegistryAuth>,
+}
+
+/// Per-registry credentials (`[registries.auth]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryAuth {
+    /// SSH private key, offered right after the ssh-agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<String>,
+    /// Environment variable holding an HTTPS token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    /// Username for SSH or for the HTTPS token (default: `git` / `x-access-token`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+/// Trusted signers of a registry (`[registries.signing]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistrySigning {
+    /// SSH `allowed_signers` file (as for `gpg.ssh.allowedSignersFile`);
+    /// every registry commit must carry an SSH signature from one of its keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_signers: Option<String>,
+    /// Minisign public keys (base64); `tools.toml.minisig` must verify with one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub minisign_keys: Vec<String>,
+}
+
+/// Reject tool/registry names that are not plain file names: they are joined
+/// to directories and written into launcher scripts
+pub fn check_name(kind: &str, name: &str) -> Result<()> {
+    let plain = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.+@".contains(c));
+    if name.is_empty() || !plain || name.starts_with(['.', '-']) || name.contains("..") {
+        return Err(TuxBoxError::ConfigError(format!("Invalid {} name '{}'", kind, name)).into());
+    }
+    Ok(())
 }
 
 fn default_priority() -> u32 {
@@ -65,18 +162,42 @@ pub enum AuthType {
 }
 
 /// Tool definition from registry
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ToolConfig {
     pub name: String,
     pub repo: String,
     pub branch: Option<String>,
     pub version: Option<String>,
+    /// Native tools: exact release tag to install instead of resolving `version`
+    pub tag: Option<String>,
+    /// Native tools: allow prereleases when resolving the release to install
+    pub prerelease: Option<bool>,
     #[serde(rename = "type")]
     pub tool_type: Option<String>,
     pub isolation: Option<IsolationStrategy>,
     pub commands: Option<Commands>,
     /// Minimum Python version requirement from registry (e.g. ">=3.8", "^3.9")
     pub python_version: Option<String>,
+    /// Python requirements file relative to the repo (default: requirements.txt)
+    pub requirements: Option<String>,
+    /// Python extras to install with the package (pyproject `[project.optional-dependencies]`)
+    pub extras: Option<Vec<String>>,
+    /// Node.js version requirement from registry (e.g. ">=18")
+    pub node_version: Option<String>,
+    /// Python package index for this tool (internal PyPI mirror)
+    pub python_index: Option<PackageIndex>,
+    /// Pinned SHA-256 digests of native binaries, keyed by platform (e.g. "linux-x86_64")
+    pub sha256: Option<HashMap<String, String>>,
+    /// Where native releases are published (default: GitHub, derived from `repo`)
+    pub release: Option<ReleaseSource>,
+    /// Native release asset patterns and binaries to install
+    pub native: Option<NativeConfig>,
+    /// Mounts, environment, network and flags for container runs
+    pub container: Option<ContainerConfig>,
+    /// Filesystem and network policy for runs outside a container
+    pub sandbox: Option<SandboxConfig>,
+    /// Signatures required on the tool's commits or tags
+    pub signing: Option<ToolSigning>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -116,6 +237,11 @@ pub fn init_config(registry_url: &str) -> Result<()> {
     let mut config = load_config().unwrap_or_else(|_| Config {
         registries: Vec::new(),
         registry_url: None,
+        self_update: None,
+        http: None,
+        python: None,
+        containers: None,
+        git: None,
     });
 
     // Add or update registry
@@ -128,13 +254,15 @@ pub fn init_config(registry_url: &str) -> Result<()> {
             url: registry_url.to_string(),
             priority: 100,
             auth_type,
+            signing: None,
+            auth: None,
         });
     }
 
     // Sort by priority (higher priority first)
     config
         .registries
-        .sort_by(|a, b| b.priority.cmp(&a.priority));
+        .sort_by_key(|r| std::cmp::Reverse(r.priority));
 
     // Write config
     let config_toml = toml::to_string_pretty(&config)?;
@@ -187,6 +315,8 @@ pub fn load_config() -> Result<Config> {
                 url: legacy_url.clone(),
                 priority: 100,
                 auth_type,
+                signing: None,
+                auth: None,
             });
         }
     }
@@ -286,6 +416,69 @@ pub fn list_tools() -> Result<()> {
     Ok(())
 }
 
+/// Show a tool's definition and, for container runs, its effective permissions
+pub fn show_tool_info(tool_name: &str) -> Result<()> {
+    use crate::docker::ContainerProfile;
+    use colored::Colorize;
+
+    let tool_config = match crate::registry::cached_tool_config(tool_name)? {
+        Some((tool_config, _)) => tool_config,
+        None => crate::runner::get_tool_config(tool_name)?,
+    };
+    let tool_type = tool_config.tool_type.as_deref().unwrap_or("unknown");
+
+    println!("{}", tool_config.name.bold());
+    println!("  Type:       {}", tool_type);
+    println!("  Repository: {}", tool_config.repo);
+    if let Some(version) = &tool_config.version {
+        println!("  Version:    {}", version);
+    }
+    if let Some(branch) = &tool_config.branch {
+        println!("  Branch:     {}", branch);
+    }
+    if let Some(commands) = &tool_config.commands {
+        println!("  Run:        {}", commands.run);
+    }
+    let tool_path = tools_dir()?.join(tool_name);
+    if tool_path.exists() {
+        println!("  Installed:  {}", tool_path.display().to_string().green());
+    } else {
+        println!("  Installed:  {}", "no".yellow());
+    }
+
+    let containerized =
+        !matches!(tool_type, "native" | "node") && !crate::build::is_build_type(Some(tool_type));
+    if !containerized {
+        println!("\n{} runs without a container", "Isolation:".bold());
+        return Ok(());
+    }
+
+    let profile = ContainerProfile::for_tool(&tool_config)?;
+    println!("\n{}", "Container profile:".bold());
+    println!("  Mounts:");
+    for mount in &profile.mounts {
+        println!("    {} {}", "•".cyan(), mount);
+    }
+    if profile.mounts.is_empty() {
+        println!("    {}", "none".dimmed());
+    }
+    let env = if profile.env.is_empty() {
+        "none".dimmed().to_string()
+    } else {
+        profile.env.join(", ")
+    };
+    println!("  Env:        {}", env);
+    println!(
+        "  Network:    {}",
+        profile.network.as_deref().unwrap_or("runtime default")
+    );
+    if !profile.flags.is_empty() {
+        println!("  Flags:      {}", profile.flags.join(" "));
+    }
+
+    Ok(())
+}
+
 /// Show TuxBox status (Phase 0/1: local info, Phase 2: with registry)
 pub fn show_status() -> Result<()> {
     use colored::Colorize;
@@ -301,6 +494,16 @@ pub fn show_status() -> Result<()> {
     let tools_dir = tools_dir()?;
     println!("Tools directory: {}", tools_dir.display());
 
+    // Execution environment
+    match crate::environment::detect_environment() {
+        crate::environment::ExecutionEnvironment::Container(runtime) => {
+            println!("Container runtime: {}", runtime.name().green())
+        }
+        crate::environment::ExecutionEnvironment::LocalVenv => {
+            println!("Container runtime: {}", "none (local venv)".yellow())
+        }
+    }
+
     // Registry status
     if let Ok(config) = load_config() {
         if !config.registries.is_empty() {
@@ -355,33 +558,44 @@ pub fn show_status() -> Result<()> {
                 let (version, branch) = if let Some(v) = native_version {
                     (v, "native".to_string())
                 } else {
-                    // git describe --tags --always → "v1.3.3" or short hash
-                    let v = std::process::Command::new("git")
-                        .args(["describe", "--tags", "--always"])
-                        .current_dir(&tool_path)
-                        .output()
-                        .ok()
-                        .filter(|o| o.status.success())
-                        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                    let repo = git2::Repository::open(&tool_path).ok();
+                    // Like `git describe --tags --always` → "v1.3.3" or short hash
+                    let v = repo
+                        .as_ref()
+                        .and_then(|r| {
+                            let mut options = git2::DescribeOptions::new();
+                            options.describe_tags().show_commit_oid_as_fallback(true);
+                            r.describe(&options).and_then(|d| d.format(None)).ok()
+                        })
                         .unwrap_or_else(|| "unknown".to_string());
-                    // git rev-parse --abbrev-ref HEAD → branch name
-                    let b = std::process::Command::new("git")
-                        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-                        .current_dir(&tool_path)
-                        .output()
-                        .ok()
-                        .filter(|o| o.status.success())
-                        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                    // Like `git rev-parse --abbrev-ref HEAD` → branch name
+                    let b = repo
+                        .as_ref()
+                        .and_then(|r| r.head().ok())
+                        .map(|h| {
+                            if h.is_branch() {
+                                h.shorthand().unwrap_or("?").to_string()
+                            } else {
+                                "HEAD".to_string()
+                            }
+                        })
                         .unwrap_or_else(|| "?".to_string());
                     (v, b)
                 };
 
+                let pinned = if crate::tool_state::ToolState::is_pinned(&tool_path) {
+                    " [pinned]".yellow().to_string()
+                } else {
+                    String::new()
+                };
+
                 println!(
-                    "  {} {}  {}  {}",
+                    "  {} {}  {}  {}{}",
                     "•".cyan(),
                     tool_name.bold(),
                     version.green(),
-                    format!("({})", branch).dimmed()
+                    format!("({})", branch).dimmed(),
+                    pinned
                 );
             }
         } else {
@@ -406,9 +620,16 @@ pub fn show_status() -> Result<()> {
 pub fn add_registry(name: &str, url: &str, priority: Option<u32>) -> Result<()> {
     use colored::Colorize;
 
+    check_name("registry", name)?;
+
     let mut config = load_config().unwrap_or_else(|_| Config {
         registries: Vec::new(),
         registry_url: None,
+        self_update: None,
+        http: None,
+        python: None,
+        containers: None,
+        git: None,
     });
 
     // Check if registry with this name already exists
@@ -432,12 +653,14 @@ pub fn add_registry(name: &str, url: &str, priority: Option<u32>) -> Result<()>
         url: url.to_string(),
         priority: priority.unwrap_or(100),
         auth_type,
+        signing: None,
+        auth: None,
     });
 
     // Sort by priority
     config
         .registries
-        .sort_by(|a, b| b.priority.cmp(&a.priority));
+        .sort_by_key(|r| std::cmp::Reverse(r.priority));
 
     // Save config
     let config_toml = toml::to_string_pretty(&config)?;
@@ -451,6 +674,65 @@ pub fn add_registry(name: &str, url: &str, priority: Option<u32>) -> Result<()>
     Ok(())
 }
 
+/// Point a registry at `url`, adding it when it is not configured yet.
+/// Used by `tbox bundle import` to switch registries to their local mirrors;
+/// `signing` applies to a registry that has no requirements configured yet.
+pub fn set_registry_url(
+    name: &str,
+    url: &str,
+    priority: u32,
+    signing: Option<RegistrySigning>,
+) -> Result<RegistryConfig> {
+    let mut config = load_config().unwrap_or_else(|_| Config {
+        registries: Vec::new(),
+        registry_url: None,
+        self_update: None,
+        http: None,
+        python: None,
+        containers: None,
+        git: None,
+    });
+
+    let auth_type = if url.starts_with("git@") || url.starts_with("ssh://") {
+        AuthType::Ssh
+    } else {
+        AuthType::Https
+    };
+
+    let registry = match config.registries.iter_mut().find(|r| r.name == name) {
+        Some(existing) => {
+            existing.url = url.to_string();
+            existing.auth_type = auth_type;
+            // Requirements configured on this host stay; a bundle only adds them
+            if existing.signing.is_none() {
+                existing.signing = signing;
+            }
+            existing.clone()
+        }
+        None => {
+            let registry = RegistryConfig {
+                name: name.to_string(),
+                url: url.to_string(),
+                priority,
+                auth_type,
+                signing,
+                auth: None,
+            };
+            config.registries.push(registry.clone());
+            registry
+        }
+    };
+
+    config
+        .registries
+        .sort_by_key(|r| std::cmp::Reverse(r.priority));
+
+    fs::create_dir_all(tuxbox_home()?)?;
+    let config_toml = toml::to_string_pretty(&config)?;
+    fs::write(config_file()?, config_toml)?;
+    Ok(registry)
+}
+
 /// Remove a registry from configuration and optionally delete its local cache
 pub fn remove_registry(name: &str, keep_cache: bool) -> Result<()> {
     use colored::Colorize;
@@ -495,6 +777,8 @@ pub fn remove_registry(name: &str, keep_cache: bool) -> Result<()> {
 pub fn rename_registry(old_name: &str, new_name: &str) -> Result<()> {
     use colored::Colorize;
 
+    check_name("registry", new_name)?;
+
     let mut config = load_config()?;
 
     // Check new name is not already taken
@@ -564,7 +848,7 @@ pub fn set_registry_priority(name: &str, priority: u32) -> Result<()> {
     // Re-sort by priority (higher first)
     config
         .registries
-        .sort_by(|a, b| b.priority.cmp(&a.priority));
+        .sort_by_key(|r| std::cmp::Reverse(r.priority));
 
     // Save config
     let config_toml = toml::to_string_pretty(&config)?;
@@ -580,6 +864,92 @@ pub fn set_registry_priority(name: &str, priority: u32) -> Result<()> {
     Ok(())
 }
 
+/// Configure (or with `signing = None`, drop) the keys a registry must be signed with
+pub fn set_registry_signing(name: &str, signing: Option<RegistrySigning>) -> Result<()> {
+    use colored::Colorize;
+
+    let mut config = load_config()?;
+    let registry = config
+        .registries
+        .iter_mut()
+        .find(|r| r.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Registry '{}' not found", name))?;
+
+    if let Some(signers) = signing.as_ref().and_then(|s| s.allowed_signers.as_ref()) {
+        let home = dirs::home_dir().unwrap_or_default();
+        let path = expand_path(signers, &home, &std::env::current_dir()?);
+        if !path.is_file() {
+            return Err(TuxBoxError::ConfigError(format!(
+                "allowed_signers file not found: {}",
+                path.display()
+            ))
+            .into());
+        }
+    }
+    registry.signing = signing;
+    let enabled = registry.signing.is_some();
+
+    let config_toml = toml::to_string_pretty(&config)?;
+    fs::write(config_file()?, config_toml)?;
+
+    if enabled {
+        println!(
+            "{} Registry '{}' now only syncs to signed revisions",
+            "✓".green(),
+            name.cyan().bold()
+        );
+    } else {
+        println!(
+            "{} Signature checks disabled for registry '{}'",
+            "✓".green(),
+            name.cyan().bold()
+        );
+    }
+    Ok(())
+}
+
+/// Configure (or with `auth = None`, drop) the credentials of a registry
+pub fn set_registry_auth(name: &str, auth: Option<RegistryAuth>) -> Result<()> {
+    use colored::Colorize;
+
+    let mut config = load_config()?;
+    let registry = config
+        .registries
+        .iter_mut()
+        .find(|r| r.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Registry '{}' not found", name))?;
+
+    if let Some(key) = auth.as_ref().and_then(|a| a.ssh_key.as_ref()) {
+        let home = dirs::home_dir().unwrap_or_default();
+        let path = expand_path(key, &home, &std::env::current_dir()?);
+        if !path.is_file() {
+            return Err(
+                TuxBoxError::ConfigError(format!("SSH key not found: {}", path.display())).into(),
+            );
+        }
+    }
+    registry.auth = auth;
+    let enabled = registry.auth.is_some();
+
+    let config_toml = toml::to_string_pretty(&config)?;
+    fs::write(config_file()?, config_toml)?;
+
+    if enabled {
+        println!(
+            "{} Credentials set for registry '{}'",
+            "✓".green(),
+            name.cyan().bold()
+        );
+    } else {
+        println!(
+            "{} Registry '{}' uses the default credentials again",
+            "✓".green(),
+            name.cyan().bold()
+        );
+    }
+    Ok(())
+}
+
 /// List all configured registries
 pub fn list_registries() -> Result<()> {
     use colored::Colorize;
@@ -615,6 +985,36 @@ pub fn list_registries() -> Result<()> {
         );
         println!("  {} URL: {}", "→".dimmed(), registry.url.dimmed());
         println!("  {} Auth: {}", "→".dimmed(), auth_label.dimmed());
+        if let Some(auth) = &registry.auth {
+            let mut settings = Vec::new();
+            if let Some(key) = &auth.ssh_key {
+                settings.push(format!("key {}", key));
+            }
+            if let Some(var) = &auth.token_env {
+                settings.push(format!("token from ${}", var));
+            }
+            if let Some(user) = &auth.username {
+                settings.push(format!("user {}", user));
+            }
+            println!(
+                "  {} Credentials: {}",
+                "→".dimmed(),
+                settings.join(", ").dimmed()
+            );
+        }
+        if let Some(signing) = &registry.signing {
+            let mut methods = Vec::new();
+            if let Some(signers) = &signing.allowed_signers {
+                methods.push(format!("SSH commits ({})", signers));
+            }
+            if !signing.minisign_keys.is_empty() {
+                methods.push(format!(
+                    "minisign tools.toml ({} key(s))",
+                    signing.minisign_keys.len()
+                ));
+            }
+            println!("  {} Signed: {}", "→".dimmed(), methods.join(", ").dimmed());
+        }
         println!();
     }
 
@@ -0,0 +1,317 @@
+//! Credentials for git2 remotes (registries and tool repositories)
+//!
+//! SSH remotes try, in order:
+//! 1. The ssh-agent (`SSH_AUTH_SOCK`)
+//! 2. The registry's `ssh_key` (`[registries.auth]`)
+//! 3. `[git] ssh_keys` in `~/.tuxbox/config.toml`
+//! 4. `IdentityFile` entries of `~/.ssh/config` for the host
+//! 5. `~/.ssh/id_ed25519`, `~/.ssh/id_ecdsa`, `~/.ssh/id_rsa`
+//!
+//! Passphrase-protected key files are skipped; load them into the agent instead.
+//! Host keys are checked by libgit2 against `~/.ssh/known_hosts` (hashed entries
+//! included); unknown hosts and changed keys are refused.
+//!
+//! HTTPS remotes try the registry's `token_env` variable, then the tokens used
+//! for release downloads (see `http`), then git's credential helpers.
+
+use anyhow::Result;
+use colored::Colorize;
+use git2::{Cred, CredentialType, ErrorClass, ErrorCode, FetchOptions, RemoteCallbacks};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, RegistryAuth};
+use crate::error::TuxBoxError;
+
+/// `[git]` section of `~/.tuxbox/config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitConfig {
+    /// SSH private keys to offer after the agent, e.g. `["~/.ssh/id_work"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ssh_keys: Vec<String>,
+}
+
+/// Fetch options for a remote: proxy auto-detection plus credential callbacks.
+/// `auth` holds the registry's own settings (`None` for tool repositories).
+pub fn fetch_options(auth: Option<&RegistryAuth>) -> FetchOptions<'static> {
+    let mut proxy_opts = git2::ProxyOptions::new();
+    proxy_opts.auto();
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.proxy_options(proxy_opts);
+    fetch_options.remote_callbacks(callbacks(auth.cloned().unwrap_or_default()));
+    fetch_options
+}
+
+fn callbacks(auth: RegistryAuth) -> RemoteCallbacks<'static> {
+    // libgit2 asks again after every rejected credential, so each source is tried once
+    let mut agent_tried = false;
+    let mut ssh_keys: Option<Vec<PathBuf>> = None;
+    let mut token_tried = false;
+    let mut helper_tried = false;
+
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username_from_url, allowed| {
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(auth.username.as_deref().unwrap_or("git"));
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let user = username_from_url
+                .or(auth.username.as_deref())
+                .unwrap_or("git");
+            if !agent_tried {
+                agent_tried = true;
+                if std::env::var_os("SSH_AUTH_SOCK").is_some() {
+                    return Cred::ssh_key_from_agent(user);
+                }
+            }
+            let keys = ssh_keys.get_or_insert_with(|| {
+                let mut keys = ssh_key_candidates(&auth, &host_of(url));
+                keys.reverse();
+                keys
+            });
+            if let Some(key) = keys.pop() {
+                let public = PathBuf::from(format!("{}.pub", key.display()));
+                let public = public.exists().then_some(public);
+                return Cred::ssh_key(user, public.as_deref(), &key, None);
+            }
+            return Err(auth_error(
+                "no SSH key was accepted (tried ssh-agent and key files)",
+            ));
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if !token_tried {
+                token_tried = true;
+                if let Some((user, token)) = https_token(&auth, url) {
+                    return Cred::userpass_plaintext(&user, &token);
+                }
+            }
+            if !helper_tried {
+                helper_tried = true;
+                if let Ok(git_config) = git2::Config::open_default()
+                    && let Ok(cred) = Cred::credential_helper(&git_config, url, username_from_url)
+                {
+                    return Ok(cred);
+                }
+            }
+            return Err(auth_error("no HTTPS credentials were accepted"));
+        }
+
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+        Err(auth_error("no supported authentication method"))
+    });
+    callbacks
+}
+
+/// Rejection from the callback; the `Auth` code lets [`git_error`] add a hint
+fn auth_error(message: &str) -> git2::Error {
+    git2::Error::new(ErrorCode::Auth, ErrorClass::Callback, message)
+}
+
+/// Token for an HTTPS remote as (username, password)
+fn https_token(auth: &RegistryAuth, url: &str) -> Option<(String, String)> {
+    let user = auth.username.as_deref();
+    if let Some(var) = &auth.token_env {
+        if let Ok(token) = std::env::var(var)
+            && !token.is_empty()
+        {
+            return Some((user.unwrap_or("x-access-token").to_string(), token));
+        }
+        eprintln!(
+            "  {} ${} is not set, trying other credentials",
+            "⚠".yellow(),
+            var
+        );
+    }
+    crate::http::git_credentials(url)
+        .map(|(login, token)| (user.map(str::to_string).unwrap_or(login), token))
+}
+
+/// SSH key files to offer, in order, that exist and are not passphrase-protected
+fn ssh_key_candidates(auth: &RegistryAuth, host: &str) -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let cwd = std::env::current_dir().unwrap_or_default();
+
+    let mut keys: Vec<String> = auth.ssh_key.iter().cloned().collect();
+    if let Some(git) = config::load_config().ok().and_then(|c| c.git) {
+        keys.extend(git.ssh_keys);
+    }
+    if let Ok(ssh_config) = std::fs::read_to_string(home.join(".ssh/config")) {
+        keys.extend(identity_files(&ssh_config, host));
+    }
+    keys.extend(
+        ["id_ed25519", "id_ecdsa", "id_rsa"]
+            .iter()
+            .map(|name| format!("~/.ssh/{}", name)),
+    );
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for key in keys {
+        let path = config::expand_path(&key, &home, &cwd);
+        if !paths.contains(&path) && path.is_file() && !is_encrypted_key(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// `IdentityFile` values of the `Host` blocks in ssh_config `content` matching `host`
+fn identity_files(content: &str, host: &str) -> Vec<String> {
+    let mut matching = true; // Lines before the first Host apply to every host
+    let mut files = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let (keyword, value) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .map(|(k, v)| (k, v.trim_start_matches([' ', '\t', '=']).trim()))
+            .unwrap_or((line, ""));
+        if keyword.eq_ignore_ascii_case("Host") {
+            let patterns: Vec<&str> = value.split_whitespace().collect();
+            let negated = patterns
+                .iter()
+                .any(|p| p.strip_prefix('!').is_some_and(|p| glob_match(p, host)));
+            matching = !negated && patterns.iter().any(|p| glob_match(p, host));
+        } else if keyword.eq_ignore_ascii_case("Match") {
+            matching = false; // Match conditions are not evaluated
+        } else if keyword.eq_ignore_ascii_case("IdentityFile") && matching {
+            files.push(value.trim_matches('"').replace("%d", "~"));
+        }
+    }
+    files
+}
+
+/// ssh_config host pattern (`*` and `?` wildcards)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn matches(p: &[u8], t: &[u8]) -> bool {
+        match (p.first(), t.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => matches(&p[1..], t) || (!t.is_empty() && matches(p, &t[1..])),
+            (Some(b'?'), Some(_)) => matches(&p[1..], &t[1..]),
+            (Some(a), Some(b)) if a.eq_ignore_ascii_case(b) => matches(&p[1..], &t[1..]),
+            _ => false,
+        }
+    }
+    matches(pattern.as_bytes(), text.as_bytes())
+}
+
+/// Passphrase-protected keys cannot be used without a prompt
+fn is_encrypted_key(path: &Path) -> bool {
+    // Base64 of "openssh-key-v1\0" followed by cipher "none"
+    const OPENSSH_UNENCRYPTED: &str = "b3BlbnNzaC1rZXktdjEAAAAABG5vbmU";
+
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return true;
+    };
+    if content.contains("BEGIN OPENSSH PRIVATE KEY") {
+        let body: String = content
+            .lines()
+            .filter(|l| !l.starts_with("-----"))
+            .collect();
+        return !body.starts_with(OPENSSH_UNENCRYPTED);
+    }
+    content.contains("ENCRYPTED")
+}
+
+/// Host of an `scheme://[user@]host[:port]/path` or scp-like `user@host:path` URL
+pub fn host_of(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    host.split(':').next().unwrap_or_default().to_string()
+}
+
+/// Turn a failed clone/fetch of `url` into an error with a hint for auth and host key problems
+pub fn git_error(action: &str, url: &str, error: git2::Error) -> anyhow::Error {
+    let host = host_of(url);
+    let hint = match (error.class(), error.code()) {
+        (_, ErrorCode::Certificate) if is_ssh_url(url) => {
+            format!(
+                "\nThe SSH host key of {0} is unknown or has changed. Check it, then add it with:\n  \
+                 ssh-keyscan {0} >> ~/.ssh/known_hosts",
+                host
+            )
+        }
+        (_, ErrorCode::Auth) | (ErrorClass::Ssh, _) if is_ssh_url(url) => {
+            "\nLoad your key into ssh-agent (ssh-add), or set `ssh_keys` under [git] \
+             in ~/.tuxbox/config.toml"
+                .to_string()
+        }
+        (_, ErrorCode::Auth) => format!(
+            "\nSet a token for '{}' under [http.tokens] (registries can also use \
+             `tbox registry set-auth <name> --token-env <VAR>`)",
+            host
+        ),
+        _ => String::new(),
+    };
+    TuxBoxError::GitError(format!(
+        "{} {} failed: {}{}",
+        action,
+        url,
+        error.message(),
+        hint
+    ))
+    .into()
+}
+
+/// SSH remote: `ssh://...` or scp-like `user@host:path`
+pub fn is_ssh_url(url: &str) -> bool {
+    url.starts_with("ssh://") || (!url.contains("://") && url.contains('@') && url.contains(':'))
+}
+
+/// Clone `url` into `dest` with credentials
+pub fn clone(
+    url: &str,
+    dest: &Path,
+    branch: Option<&str>,
+    auth: Option<&RegistryAuth>,
+) -> Result<git2::Repository> {
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fetch_options(auth));
+    if let Some(branch) = branch {
+        builder.branch(branch);
+    }
+    builder
+        .clone(url, dest)
+        .map_err(|e| git_error("Clone of", url, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_files() {
+        let ssh_config = "\
+IdentityFile ~/.ssh/id_global
+Host github.com gitlab.*
+    IdentityFile ~/.ssh/id_public
+Host *.corp.example !legacy.corp.example
+    User git
+    IdentityFile=\"~/.ssh/id_corp\"
+Match host foo
+    IdentityFile ~/.ssh/id_match
+";
+        assert_eq!(
+            identity_files(ssh_config, "github.com"),
+            ["~/.ssh/id_global", "~/.ssh/id_public"]
+        );
+        assert_eq!(
+            identity_files(ssh_config, "git.corp.example"),
+            ["~/.ssh/id_global", "~/.ssh/id_corp"]
+        );
+        assert_eq!(
+            identity_files(ssh_config, "legacy.corp.example"),
+            ["~/.ssh/id_global"]
+        );
+        assert_eq!(host_of("ssh://git@host.example:2222/repo"), "host.example");
+        assert_eq!(host_of("git@github.com:repo.git"), "github.com");
+        assert!(is_ssh_url("git@github.com:owner/repo.git"));
+        assert!(is_ssh_url("ssh://git@host:2222/repo"));
+        assert!(!is_ssh_url("https://user@host/repo"));
+        assert!(!is_ssh_url("/srv/git/repo"));
+    }
+}
@@ -1,18 +1,340 @@
-//! Docker container management for tool isolation
+//! Container management for tool isolation (docker, podman or nerdctl)
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use std::path::Path;
-use std::process::Command;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
-use crate::config::ToolConfig;
+use crate::config::{self, ToolConfig, expand_path};
+use crate::environment::ContainerRuntime;
+use crate::error::TuxBoxError;
+use crate::{checksum, git, python};
 
-/// Run a Python tool inside a Docker container
-pub fn run_in_docker(tool_config: &ToolConfig, tool_path: &Path, args: &[String]) -> Result<()> {
-    println!("  {} Using Docker for isolated execution", "🐳".cyan());
+/// Python version used for generated images when the tool declares none
+const DEFAULT_PYTHON_VERSION: &str = "3.14";
 
-    // Determine Python version from tool or use default
-    let python_version = "3.14"; // TODO: detect from tool config or pyproject.toml
+/// Base image for generated images of bash/script tools
+const BASH_BASE_IMAGE: &str = "debian:stable-slim";
+
+/// Extra `run` flags a registry may declare (`--flag` or `--flag=value`);
+/// anything else (`--privileged`, `-v`, `--cap-add`, ...) is left to the
+/// user's `[containers.<tool>]`
+const REGISTRY_FLAGS: &[&str] = &[
+    "--read-only",
+    "--init",
+    "--cap-drop",
+    "--memory",
+    "--memory-swap",
+    "--cpus",
+    "--pids-limit",
+    "--shm-size",
+    "--ulimit",
+    "--tmpfs",
+    "--stop-timeout",
+    "--security-opt=no-new-privileges",
+];
+
+/// Container permissions: `[tools.<tool>.container]` in the registry and
+/// `[containers.<tool>]` in the user's config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContainerConfig {
+    /// Bind mounts: `"~/.ssh:ro"`, `"$PWD"`, `"/data:/mnt/data:ro"`
+    #[serde(default)]
+    pub mounts: Vec<String>,
+    /// Host environment variables passed into the container
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Network mode: `none`, `host` or `bridge` (runtime default when unset)
+    pub network: Option<String>,
+    /// Extra arguments for `<runtime> run`
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+/// A bind mount of a host path into the container
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mount {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub read_only: bool,
+}
+
+impl Mount {
+    /// Parse `source[:target][:ro|rw]`, expanding a leading `~`, `$HOME` or `$PWD`
+    fn parse(spec: &str, home: &Path, cwd: &Path) -> Result<Self> {
+        let expand = |path: &str| expand_path(path, home, cwd);
+
+        let mut parts: Vec<&str> = spec.split(':').collect();
+        let read_only = match parts.last() {
+            Some(&"ro") if parts.len() > 1 => {
+                parts.pop();
+                true
+            }
+            Some(&"rw") if parts.len() > 1 => {
+                parts.pop();
+                false
+            }
+            _ => false,
+        };
+        let (source, target) = match parts.as_slice() {
+            [source] => (expand(source), expand(source)),
+            [source, target] => (expand(source), expand(target)),
+            _ => {
+                return Err(TuxBoxError::ConfigError(format!(
+                    "Invalid mount '{}' (expected source[:target][:ro])",
+                    spec
+                ))
+                .into());
+            }
+        };
+        if !source.is_absolute() || !target.is_absolute() {
+            return Err(TuxBoxError::ConfigError(format!(
+                "Mount '{}' must use absolute paths (or ~, $HOME, $PWD)",
+                spec
+            ))
+            .into());
+        }
+        Ok(Mount {
+            source,
+            target,
+            read_only,
+        })
+    }
+
+    /// What a registry may mount on its own: the current directory, or
+    /// read-only paths below it or below home, never a runtime socket
+    fn is_registry_safe(&self, home: &Path, cwd: &Path) -> bool {
+        use std::path::Component;
+
+        let source = &self.source;
+        if source.components().any(|c| c == Component::ParentDir) {
+            return false;
+        }
+        if source == cwd {
+            return true;
+        }
+        let below = |dir: &Path| source.starts_with(dir) && source != dir;
+        let socket = source.extension().is_some_and(|e| e == "sock") || {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::FileTypeExt;
+                std::fs::metadata(source).is_ok_and(|m| m.file_type().is_socket())
+            }
+            #[cfg(not(unix))]
+            false
+        };
+        self.read_only && (below(cwd) || below(home)) && !socket
+    }
+
+    fn to_arg(&self) -> String {
+        format!(
+            "{}:{}{}",
+            self.source.display(),
+            self.target.display(),
+            if self.read_only { ":ro" } else { "" }
+        )
+    }
+}
+
+impl std::fmt::Display for Mount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.source == self.target {
+            write!(f, "{}", self.source.display())?;
+        } else {
+            write!(f, "{} → {}", self.source.display(), self.target.display())?;
+        }
+        write!(f, " ({})", if self.read_only { "ro" } else { "rw" })
+    }
+}
+
+/// Effective container permissions of a tool
+#[derive(Debug, PartialEq)]
+pub struct ContainerProfile {
+    pub mounts: Vec<Mount>,
+    pub env: Vec<String>,
+    pub network: Option<String>,
+    pub flags: Vec<String>,
+}
+
+impl ContainerProfile {
+    /// Profile of a tool: the registry's `container` section (or only the
+    /// current directory when it declares no mounts), widened by the user's
+    /// `[containers.<tool>]` config
+    pub fn for_tool(tool_config: &ToolConfig) -> Result<Self> {
+        let user = config::load_config()
+            .ok()
+            .and_then(|c| c.containers)
+            .and_then(|mut containers| containers.remove(&tool_config.name));
+        let home = dirs::home_dir().unwrap_or_default();
+        let cwd = std::env::current_dir().context("Cannot determine current directory")?;
+        Self::resolve(tool_config.container.as_ref(), user.as_ref(), &home, &cwd)
+    }
+
+    fn resolve(
+        declared: Option<&ContainerConfig>,
+        user: Option<&ContainerConfig>,
+        home: &Path,
+        cwd: &Path,
+    ) -> Result<Self> {
+        let declared = declared.cloned().unwrap_or_default();
+        let user = user.cloned().unwrap_or_default();
+
+        if let Some(flag) = declared.flags.iter().find(|f| !is_registry_flag(f)) {
+            return Err(registry_refused("flag", flag));
+        }
+
+        // Safe default: nothing but the directory tbox was started from
+        let default_mounts = vec!["$PWD".to_string()];
+        let declared_mounts = if declared.mounts.is_empty() {
+            &default_mounts
+        } else {
+            &declared.mounts
+        };
+
+        let mut mounts: Vec<Mount> = Vec::new();
+        for (index, spec) in declared_mounts.iter().chain(&user.mounts).enumerate() {
+            let mount = Mount::parse(spec, home, cwd)?;
+            if index < declared_mounts.len() && !mount.is_registry_safe(home, cwd) {
+                return Err(registry_refused("mount", spec));
+            }
+            // A later (user) entry for the same target replaces the earlier one
+            mounts.retain(|m| m.target != mount.target);
+            mounts.push(mount);
+        }
+
+        if let Some(name) = declared.env.iter().find(|name| is_secret_env(name)) {
+            return Err(registry_refused("environment variable", name));
+        }
+        let mut env = declared.env;
+        for name in user.env {
+            if !env.contains(&name) {
+                env.push(name);
+            }
+        }
+
+        Ok(ContainerProfile {
+            mounts,
+            env,
+            network: user.network.or(declared.network),
+            flags: declared.flags.into_iter().chain(user.flags).collect(),
+        })
+    }
+
+    /// Where `path` appears inside the container, if a mount covers it
+    fn container_path(&self, path: &Path) -> Option<PathBuf> {
+        self.mounts
+            .iter()
+            .filter_map(|m| {
+                let rest = path.strip_prefix(&m.source).ok()?;
+                Some((m.source.components().count(), m.target.join(rest)))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, path)| path)
+    }
+
+    /// `<runtime> run` arguments for this profile
+    fn run_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for mount in &self.mounts {
+            if !mount.source.exists() {
+                eprintln!(
+                    "  {} Skipping mount of missing {}",
+                    "⚠".yellow(),
+                    mount.source.display()
+                );
+                continue;
+            }
+            args.push("-v".to_string());
+            args.push(mount.to_arg());
+        }
+        for name in &self.env {
+            // `-e NAME` copies the value from tbox's environment, if set
+            if std::env::var_os(name).is_some() {
+                args.push("-e".to_string());
+                args.push(name.clone());
+            }
+        }
+        if let Some(network) = &self.network {
+            args.push("--network".to_string());
+            args.push(network.clone());
+        }
+        args.extend(self.flags.iter().cloned());
+        args
+    }
+}
+
+/// A registry asked for more than it may grant itself
+fn registry_refused(what: &str, value: &str) -> anyhow::Error {
+    TuxBoxError::ConfigError(format!(
+        "Container {} '{}' is not allowed in a registry. \
+         If you trust it, add it under [containers.<tool>] in your config",
+        what, value
+    ))
+    .into()
+}
+
+/// Environment variables a registry may not forward: anything that looks
+/// like a credential (`AWS_SECRET_ACCESS_KEY`, `GITHUB_TOKEN`, `SSH_AUTH_SOCK`)
+fn is_secret_env(name: &str) -> bool {
+    const SECRET_PARTS: &[&str] = &[
+        "SECRET",
+        "TOKEN",
+        "PASS",
+        "KEY",
+        "CREDENTIAL",
+        "AUTH",
+        "SESSION",
+        "COOKIE",
+        "PRIVATE",
+    ];
+    let name = name.to_ascii_uppercase();
+    SECRET_PARTS.iter().any(|part| name.contains(part))
+}
+
+fn is_registry_flag(flag: &str) -> bool {
+    REGISTRY_FLAGS.iter().any(|allowed| {
+        flag == *allowed
+            || (!allowed.contains('=')
+                && flag
+                    .strip_prefix(allowed)
+                    .is_some_and(|value| value.starts_with('=')))
+    })
+}
+
+/// How the image for a tool without its own Dockerfile is generated
+#[derive(Debug, PartialEq)]
+enum ImageKind {
+    /// `python:<version>-slim` with the tool's requirements installed
+    Python { version: String },
+    /// Plain Debian image with bash for `bash`/`script` tools
+    Bash,
+}
+
+impl ImageKind {
+    fn for_tool(tool_config: &ToolConfig, tool_path: &Path) -> Self {
+        match tool_config.tool_type.as_deref() {
+            Some("bash") | Some("script") => ImageKind::Bash,
+            _ => ImageKind::Python {
+                version: python_image_version(tool_config, tool_path),
+            },
+        }
+    }
+}
+
+/// Run a tool inside a Docker container
+pub fn run_in_docker(
+    runtime: ContainerRuntime,
+    tool_config: &ToolConfig,
+    tool_path: &Path,
+    args: &[String],
+) -> Result<()> {
+    println!(
+        "  {} Using {} for isolated execution",
+        "🐳".cyan(),
+        runtime.name()
+    );
+
+    let kind = ImageKind::for_tool(tool_config, tool_path);
 
     // Build container image name
     let image_name = format!("tuxbox-{}", tool_config.name);
@@ -20,26 +342,475 @@ pub fn run_in_docker(tool_config: &ToolConfig, tool_path: &Path, args: &[String]
     // Check if tool has a custom Dockerfile
     let has_custom_dockerfile = tool_path.join("Dockerfile").exists();
 
-    // Check if we need to build the image
-    if !image_exists(&image_name)? {
-        build_image(&image_name, tool_path, python_version)?;
+    // Build the image when it is missing or was built from another revision
+    let inputs = ImageInputs::current(tool_config, tool_path, &kind)?;
+    match built_from(runtime, &image_name)? {
+        Some(existing) if existing == inputs => {}
+        Some(_) => {
+            println!(
+                "  {} Tool changed since the image was built, rebuilding...",
+                "→".cyan()
+            );
+            build_image(runtime, &image_name, tool_path, &kind, &inputs, false)?;
+        }
+        None => build_image(runtime, &image_name, tool_path, &kind, &inputs, false)?,
     }
 
     // Run the tool in the container
     run_container(
+        runtime,
         &image_name,
         tool_config,
         tool_path,
         args,
+        &kind,
         has_custom_dockerfile,
     )?;
 
     Ok(())
 }
 
+/// Rebuild a tool's image unconditionally (`tbox rebuild`)
+pub fn rebuild_image(
+    runtime: ContainerRuntime,
+    tool_config: &ToolConfig,
+    tool_path: &Path,
+    no_cache: bool,
+) -> Result<()> {
+    let kind = ImageKind::for_tool(tool_config, tool_path);
+    let inputs = ImageInputs::current(tool_config, tool_path, &kind)?;
+    build_image(
+        runtime,
+        &format!("tuxbox-{}", tool_config.name),
+        tool_path,
+        &kind,
+        &inputs,
+        no_cache,
+    )
+}
+
+/// What an image was built from, stored as image labels
+#[derive(Debug, PartialEq)]
+struct ImageInputs {
+    tool: String,
+    /// HEAD commit of the tool's repository
+    commit: String,
+    /// SHA-256 of the Dockerfile (the tool's own or the generated one)
+    dockerfile_hash: String,
+}
+
+impl ImageInputs {
+    const TOOL_LABEL: &str = "tuxbox.tool";
+    const COMMIT_LABEL: &str = "tuxbox.commit";
+    const DOCKERFILE_LABEL: &str = "tuxbox.dockerfile";
+
+    fn current(tool_config: &ToolConfig, tool_path: &Path, kind: &ImageKind) -> Result<Self> {
+        let custom = tool_path.join("Dockerfile");
+        let dockerfile = if custom.exists() {
+            std::fs::read(&custom).context("Failed to read Dockerfile")?
+        } else {
+            generated_dockerfile(kind).into_bytes()
+        };
+        Ok(ImageInputs {
+            tool: tool_config.name.clone(),
+            commit: git::head_commit(tool_path)?,
+            dockerfile_hash: checksum::sha256_hex(&dockerfile),
+        })
+    }
+
+    fn labels(&self) -> [String; 3] {
+        [
+            format!("{}={}", Self::TOOL_LABEL, self.tool),
+            format!("{}={}", Self::COMMIT_LABEL, self.commit),
+            format!("{}={}", Self::DOCKERFILE_LABEL, self.dockerfile_hash),
+        ]
+    }
+
+    fn from_labels(labels: &serde_json::Value) -> Self {
+        let label = |key: &str| labels[key].as_str().unwrap_or_default().to_string();
+        ImageInputs {
+            tool: label(Self::TOOL_LABEL),
+            commit: label(Self::COMMIT_LABEL),
+            dockerfile_hash: label(Self::DOCKERFILE_LABEL),
+        }
+    }
+}
+
+/// Labels of an existing image, or None when the image does not exist.
+/// Images built before labels were introduced yield empty inputs (never current).
+fn built_from(runtime: ContainerRuntime, image_name: &str) -> Result<Option<ImageInputs>> {
+    if !image_exists(runtime, image_name)? {
+        return Ok(None);
+    }
+    let output = runtime
+        .command()
+        .args([
+            "image",
+            "inspect",
+            "--format",
+            "{{json .Config.Labels}}",
+            image_name,
+        ])
+        .output()
+        .context("Failed to inspect Docker image")?;
+    let labels: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap_or(serde_json::Value::Null);
+    Ok(Some(ImageInputs::from_labels(&labels)))
+}
+
+/// Image ID of `image`, if it exists
+fn image_id(runtime: ContainerRuntime, image: &str) -> Option<String> {
+    let output = runtime
+        .command()
+        .args(["image", "inspect", "--format", "{{.Id}}", image])
+        .output()
+        .ok()?;
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !id.is_empty()).then_some(id)
+}
+
+/// Remove images of the tool superseded by `image_name` (best-effort: images
+/// still used by a running container are kept)
+fn prune_superseded(
+    runtime: ContainerRuntime,
+    tool_name: &str,
+    image_name: &str,
+    previous: Option<String>,
+) {
+    let Some(current) = image_id(runtime, image_name) else {
+        return;
+    };
+    let labeled = runtime
+        .command()
+        .args([
+            "images",
+            "-q",
+            "--no-trunc",
+            "--filter",
+            &format!("label={}={}", ImageInputs::TOOL_LABEL, tool_name),
+        ])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+
+    let mut stale: Vec<String> = labeled
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && *id != current)
+        .map(str::to_string)
+        .collect();
+    if let Some(previous) = previous.filter(|id| *id != current && !stale.contains(id)) {
+        stale.push(previous);
+    }
+
+    let removed = stale
+        .iter()
+        .filter(|id| {
+            runtime
+                .command()
+                .args(["rmi", id.as_str()])
+                .output()
+                .is_ok_and(|o| o.status.success())
+        })
+        .count();
+    if removed > 0 {
+        println!("  {} Removed {} superseded image(s)", "✓".green(), removed);
+    }
+}
+
+/// Labels identifying a running tool container
+#[derive(Debug, Clone, PartialEq)]
+struct RunLabels {
+    tool: String,
+    version: String,
+    pid: u32,
+    /// Start time (seconds since the Unix epoch)
+    started: u64,
+}
+
+impl RunLabels {
+    const VERSION_LABEL: &str = "tuxbox.version";
+    const PID_LABEL: &str = "tuxbox.pid";
+    const STARTED_LABEL: &str = "tuxbox.started";
+
+    fn new(tool_config: &ToolConfig) -> Self {
+        RunLabels {
+            tool: tool_config.name.clone(),
+            version: tool_config.version.clone().unwrap_or_default(),
+            pid: std::process::id(),
+            started: unix_now(),
+        }
+    }
+
+    /// `tuxbox-<tool>-<pid>-<started>`, restricted to characters container names allow
+    fn container_name(&self) -> String {
+        let tool: String = self
+            .tool
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "_.-".contains(c) {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        format!("tuxbox-{}-{}-{}", tool, self.pid, self.started)
+    }
+
+    fn labels(&self) -> [String; 4] {
+        [
+            format!("{}={}", ImageInputs::TOOL_LABEL, self.tool),
+            format!("{}={}", Self::VERSION_LABEL, self.version),
+            format!("{}={}", Self::PID_LABEL, self.pid),
+            format!("{}={}", Self::STARTED_LABEL, self.started),
+        ]
+    }
+
+    /// None for containers not started by `tbox run` (e.g. image labels only)
+    fn from_labels(labels: &serde_json::Value) -> Option<Self> {
+        let label = |key: &str| labels[key].as_str().map(str::to_string);
+        Some(RunLabels {
+            tool: label(ImageInputs::TOOL_LABEL)?,
+            version: label(Self::VERSION_LABEL).unwrap_or_default(),
+            pid: label(Self::PID_LABEL)?.parse().ok()?,
+            started: label(Self::STARTED_LABEL)?.parse().ok()?,
+        })
+    }
+}
+
+/// Running containers started by `tbox run`, optionally only those of `tool`,
+/// as (container name, labels)
+fn running_containers(
+    runtime: ContainerRuntime,
+    tool: Option<&str>,
+) -> Result<Vec<(String, RunLabels)>> {
+    let filter = match tool {
+        Some(tool) => format!("label={}={}", ImageInputs::TOOL_LABEL, tool),
+        None => format!("label={}", RunLabels::PID_LABEL),
+    };
+    let output = runtime
+        .command()
+        .args(["ps", "-q", "--no-trunc", "--filter", &filter])
+        .output()
+        .with_context(|| format!("Failed to run {} ps", runtime.name()))?;
+    let ids: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect();
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = runtime
+        .command()
+        .args(["inspect", "--format", "{{.Name}}\t{{json .Config.Labels}}"])
+        .args(&ids)
+        .output()
+        .with_context(|| format!("Failed to run {} inspect", runtime.name()))?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, labels) = line.split_once('\t')?;
+            let labels: serde_json::Value = serde_json::from_str(labels).ok()?;
+            Some((
+                name.trim_start_matches('/').to_string(),
+                RunLabels::from_labels(&labels)?,
+            ))
+        })
+        .collect())
+}
+
+/// `tbox ps`: list running tool containers
+pub fn list_containers(runtime: ContainerRuntime) -> Result<()> {
+    let mut containers = running_containers(runtime, None)?;
+    if containers.is_empty() {
+        println!("{}", "No tbox containers running.".dimmed());
+        return Ok(());
+    }
+    containers.sort_by(|a, b| (&a.1.tool, a.1.started).cmp(&(&b.1.tool, b.1.started)));
+
+    let now = unix_now();
+    println!(
+        "{:<20} {:<12} {:<8} {:<8} {}",
+        "TOOL".bold(),
+        "VERSION".bold(),
+        "PID".bold(),
+        "UP".bold(),
+        "CONTAINER".bold()
+    );
+    for (name, run) in containers {
+        println!(
+            "{:<20} {:<12} {:<8} {:<8} {}",
+            run.tool.green(),
+            if run.version.is_empty() {
+                "-"
+            } else {
+                &run.version
+            },
+            run.pid,
+            format_age(now.saturating_sub(run.started)),
+            name.dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// `tbox kill <tool>`: stop every running container of a tool
+pub fn stop_containers(runtime: ContainerRuntime, tool: &str) -> Result<()> {
+    let containers = running_containers(runtime, Some(tool))?;
+    if containers.is_empty() {
+        println!("  {} No running containers for {}", "→".dimmed(), tool);
+        return Ok(());
+    }
+
+    let names: Vec<&str> = containers.iter().map(|(name, _)| name.as_str()).collect();
+    let status = runtime
+        .command()
+        .arg("stop")
+        .args(&names)
+        .stdout(std::process::Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run {} stop", runtime.name()))?;
+    if !status.success() {
+        anyhow::bail!("{} stop failed for {}", runtime.name(), names.join(", "));
+    }
+
+    println!(
+        "  {} Stopped {} container(s) of {}",
+        "✓".green(),
+        names.len(),
+        tool.bold()
+    );
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m", seconds / 60),
+        3600..86400 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+/// Python version for the generated image: the registry's `python_version`,
+/// else pyproject's `requires-python`, reduced to its minimum `major.minor`
+fn python_image_version(tool_config: &ToolConfig, tool_path: &Path) -> String {
+    tool_config
+        .python_version
+        .clone()
+        .or_else(|| python::pyproject_requires_python(tool_path))
+        .as_deref()
+        .and_then(python::min_required_python)
+        .map(|(major, minor)| format!("{}.{}", major, minor))
+        .unwrap_or_else(|| DEFAULT_PYTHON_VERSION.to_string())
+}
+
+/// Command run in a generated image, before the user's arguments.
+///
+/// Python tools run `commands.run` split into argv (default
+/// `python3 -m <tool>`); bash tools pass it unchanged to `bash -c` like they do
+/// outside Docker, with the user's arguments passed as `"$@"`. The container
+/// runs in the user's directory, so the tool's own files named there
+/// (`./check.sh`, `main.py`) are addressed under /app.
+fn container_command(tool_config: &ToolConfig, kind: &ImageKind, tool_path: &Path) -> Vec<String> {
+    let in_app = |word: &str| -> Option<String> {
+        let relative = word.strip_prefix("./").unwrap_or(word);
+        (!word.starts_with(['-', '/'])
+            && !relative.is_empty()
+            && tool_path.join(relative).is_file())
+        .then(|| format!("/app/{}", relative))
+    };
+    let run = tool_config.commands.as_ref().map(|c| c.run.as_str());
+    match kind {
+        ImageKind::Python { .. } => match run {
+            Some(run) => split_command(run)
+                .into_iter()
+                .map(|word| in_app(&word).unwrap_or(word))
+                .collect(),
+            None => vec![
+                "python3".to_string(),
+                "-m".to_string(),
+                tool_config.name.replace('-', "_"),
+            ],
+        },
+        ImageKind::Bash => {
+            let run = run.unwrap_or("bash");
+            // Only a leading `./script` is rewritten; the rest is shell syntax
+            let script = run
+                .split_whitespace()
+                .next()
+                .filter(|word| word.starts_with("./"))
+                .and_then(|word| in_app(word).map(|path| (word.len(), path)));
+            let run = match script {
+                Some((len, path)) => format!("{}{}", path, &run.trim_start()[len..]),
+                None => run.to_string(),
+            };
+            vec![
+                "bash".to_string(),
+                "-c".to_string(),
+                format!("{} \"$@\"", run),
+                tool_config.name.clone(),
+            ]
+        }
+    }
+}
+
+/// Split a command line into argv, honouring single/double quotes and
+/// backslash escapes (no variable or glob expansion)
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
 /// Check if a Docker image exists locally
-fn image_exists(image_name: &str) -> Result<bool> {
-    let output = Command::new("docker")
+fn image_exists(runtime: ContainerRuntime, image_name: &str) -> Result<bool> {
+    let output = runtime
+        .command()
         .args(["images", "-q", image_name])
         .output()
         .context("Failed to check Docker image")?;
@@ -47,32 +818,100 @@ fn image_exists(image_name: &str) -> Result<bool> {
     Ok(!output.stdout.is_empty())
 }
 
-/// Build Docker image for the tool
-fn build_image(image_name: &str, tool_path: &Path, python_version: &str) -> Result<()> {
+/// Write a tool's image to `dest` with `docker save`; false when there is no image
+pub fn save_image(runtime: ContainerRuntime, tool_name: &str, dest: &Path) -> Result<bool> {
+    let image_name = format!("tuxbox-{}", tool_name);
+    if !image_exists(runtime, &image_name)? {
+        return Ok(false);
+    }
+
+    let status = runtime
+        .command()
+        .args(["save", "-o"])
+        .arg(dest)
+        .arg(&image_name)
+        .status()
+        .context("Failed to run docker save")?;
+    if !status.success() {
+        anyhow::bail!("docker save failed for {}", image_name);
+    }
+    Ok(true)
+}
+
+/// Load an image archive written by [`save_image`]
+pub fn load_image(runtime: ContainerRuntime, archive: &Path) -> Result<()> {
+    let status = runtime
+        .command()
+        .args(["load", "-q", "-i"])
+        .arg(archive)
+        .status()
+        .context("Failed to run docker load")?;
+    if !status.success() {
+        anyhow::bail!("docker load failed for {}", archive.display());
+    }
+    Ok(())
+}
+
+/// Build Docker image for the tool, labeled with its inputs, then drop the
+/// images it supersedes
+fn build_image(
+    runtime: ContainerRuntime,
+    image_name: &str,
+    tool_path: &Path,
+    kind: &ImageKind,
+    inputs: &ImageInputs,
+    no_cache: bool,
+) -> Result<()> {
     println!("  {} Building Docker image...", "→".cyan());
 
+    let previous = image_id(runtime, image_name);
+
     // Check if tool has a Dockerfile
     let dockerfile_path = tool_path.join("Dockerfile");
 
     if dockerfile_path.exists() {
         // Use tool's Dockerfile
-        build_from_dockerfile(image_name, tool_path)?;
+        docker_build(
+            runtime,
+            image_name,
+            tool_path,
+            "Dockerfile",
+            inputs,
+            no_cache,
+        )
+        .context("Failed to build Docker image from Dockerfile")?;
     } else {
-        // Generate a standard Dockerfile for Python tools
-        build_standard_python_image(image_name, tool_path, python_version)?;
+        // Generate a standard Dockerfile for the tool type
+        build_generated_image(runtime, image_name, tool_path, kind, inputs, no_cache)?;
     }
 
     println!("  {} Image built successfully", "✓".green());
+    prune_superseded(runtime, &inputs.tool, image_name, previous);
     Ok(())
 }
 
-/// Build from existing Dockerfile
-fn build_from_dockerfile(image_name: &str, tool_path: &Path) -> Result<()> {
-    let status = Command::new("docker")
-        .args(["build", "-t", image_name, "."])
+/// Run `docker build` in the tool directory
+fn docker_build(
+    runtime: ContainerRuntime,
+    image_name: &str,
+    tool_path: &Path,
+    dockerfile: &str,
+    inputs: &ImageInputs,
+    no_cache: bool,
+) -> Result<()> {
+    let mut cmd = runtime.command();
+    cmd.args(["build", "-f", dockerfile, "-t", image_name]);
+    for label in inputs.labels() {
+        cmd.args(["--label", &label]);
+    }
+    if no_cache {
+        cmd.args(["--no-cache", "--pull"]);
+    }
+    let status = cmd
+        .arg(".")
         .current_dir(tool_path)
         .status()
-        .context("Failed to build Docker image from Dockerfile")?;
+        .context("Failed to run docker build")?;
 
     if !status.success() {
         anyhow::bail!("Docker build failed");
@@ -81,15 +920,11 @@ fn build_from_dockerfile(image_name: &str, tool_path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Build standard Python image with auto-install requirements
-fn build_standard_python_image(
-    image_name: &str,
-    tool_path: &Path,
-    python_version: &str,
-) -> Result<()> {
-    // Create temporary Dockerfile
-    let dockerfile_content = format!(
-        r#"FROM python:{}-slim
+/// Dockerfile generated for tools that do not ship their own
+fn generated_dockerfile(kind: &ImageKind) -> String {
+    match kind {
+        ImageKind::Python { version } => format!(
+            r#"FROM python:{}-slim
 
 WORKDIR /app
 
@@ -109,57 +944,113 @@ RUN if [ -f pyproject.toml ]; then \
 # Set entrypoint
 CMD ["python3"]
 "#,
-        python_version
-    );
+            version
+        ),
+        ImageKind::Bash => format!(
+            r#"FROM {}
+
+RUN apt-get update \
+    && apt-get install -y --no-install-recommends bash ca-certificates curl \
+    && rm -rf /var/lib/apt/lists/*
+
+WORKDIR /app
+
+# Copy tool files
+COPY . /app
+RUN chmod -R a+rX /app
+
+CMD ["bash"]
+"#,
+            BASH_BASE_IMAGE
+        ),
+    }
+}
+
+/// Build an image from the generated Dockerfile
+fn build_generated_image(
+    runtime: ContainerRuntime,
+    image_name: &str,
+    tool_path: &Path,
+    kind: &ImageKind,
+    inputs: &ImageInputs,
+    no_cache: bool,
+) -> Result<()> {
+    if let ImageKind::Python { version } = kind {
+        println!("  {} Base image: python:{}-slim", "→".cyan(), version);
+    }
 
     // Write temporary Dockerfile
     let dockerfile_path = tool_path.join("Dockerfile.tuxbox");
-    std::fs::write(&dockerfile_path, dockerfile_content).context("Failed to write Dockerfile")?;
+    std::fs::write(&dockerfile_path, generated_dockerfile(kind))
+        .context("Failed to write Dockerfile")?;
 
     // Build image
-    let status = Command::new("docker")
-        .args(["build", "-f", "Dockerfile.tuxbox", "-t", image_name, "."])
-        .current_dir(tool_path)
-        .status()
-        .context("Failed to build Docker image")?;
+    let result = docker_build(
+        runtime,
+        image_name,
+        tool_path,
+        "Dockerfile.tuxbox",
+        inputs,
+        no_cache,
+    );
 
     // Clean up temporary Dockerfile
     let _ = std::fs::remove_file(dockerfile_path);
 
-    if !status.success() {
-        anyhow::bail!("Docker build failed");
-    }
+    result
+}
 
-    Ok(())
+/// Flags running the container as the calling user, so files written to the
+/// mounted home keep their owner
+fn user_flags(runtime: ContainerRuntime, home: &str) -> Vec<String> {
+    match (runtime, runtime.is_rootless()) {
+        // keep-id maps the calling user to the same uid:gid inside the container
+        (ContainerRuntime::Podman, true) => vec!["--userns=keep-id".to_string()],
+        // Rootless Docker/nerdctl: container root already is the calling user
+        (_, true) => Vec::new(),
+        (_, false) => {
+            // Get current user's UID and GID
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                std::fs::metadata(home)
+                    .map(|m| vec!["--user".to_string(), format!("{}:{}", m.uid(), m.gid())])
+                    .unwrap_or_default()
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = home;
+                Vec::new()
+            }
+        }
+    }
 }
 
-/// Run the tool in a Docker container
+/// Run the tool in a container
 fn run_container(
+    runtime: ContainerRuntime,
     image_name: &str,
     tool_config: &ToolConfig,
-    _tool_path: &Path,
+    tool_path: &Path,
     args: &[String],
+    kind: &ImageKind,
     has_custom_dockerfile: bool,
 ) -> Result<()> {
     println!("  {} Running in container...", "→".cyan());
 
-    // Prepare command to run inside container
-    // For Python modules: python3 -m <module_name>
-    let container_cmd = format!("python3 -m {}", tool_config.name);
-
-    // Build container name: <tool>_<version> (e.g., sshmenuc_1.1.0)
-    let container_name = if let Some(version) = &tool_config.version {
-        format!("{}_{}", tool_config.name, version)
-    } else {
-        tool_config.name.clone()
-    };
+    // Unique per invocation, so concurrent runs of the same tool do not clash;
+    // `tbox ps` / `tbox kill` find the container through its labels
+    let run = RunLabels::new(tool_config);
 
     // Build docker run command
-    let mut cmd = Command::new("docker");
+    let mut cmd = runtime.command();
     cmd.arg("run");
     cmd.arg("--rm"); // Remove container after exit
     cmd.arg("--name");
-    cmd.arg(&container_name);
+    cmd.arg(run.container_name());
+    for label in run.labels() {
+        cmd.args(["--label", &label]);
+    }
 
     // Check if stdout is a TTY - if yes, use -it for interactive tools
     use std::io::IsTerminal;
@@ -175,40 +1066,42 @@ fn run_container(
     // Only run as non-root user for auto-generated Dockerfiles
     // For custom Dockerfiles, trust the tool's setup (may use /root/.local for packages)
     if !has_custom_dockerfile {
-        // Get current user's UID and GID
-        #[cfg(unix)]
-        let user_id = {
-            use std::os::unix::fs::MetadataExt;
-            let metadata = std::fs::metadata(&home).ok();
-            metadata.map(|m| format!("{}:{}", m.uid(), m.gid()))
-        };
-
-        // Run container as current user (preserves permissions and home structure)
-        #[cfg(unix)]
-        if let Some(uid_gid) = user_id {
-            cmd.arg("--user");
-            cmd.arg(&uid_gid);
-        }
+        cmd.args(user_flags(runtime, &home));
     }
 
-    // Set HOME environment variable for auto-generated Dockerfiles only
-    // Custom Dockerfiles may rely on /root for package locations
-    if !has_custom_dockerfile {
+    let profile = ContainerProfile::for_tool(tool_config)?;
+
+    // Auto-generated images: HOME keeps the host path (so `~` mounts line up)
+    // but is a scratch tmpfs, writable by the non-root user, unless a mount
+    // already provides it. Custom Dockerfiles may rely on /root for packages.
+    if !has_custom_dockerfile && !home.is_empty() {
         cmd.arg("-e");
         cmd.arg(format!("HOME={}", home));
+        if !profile
+            .mounts
+            .iter()
+            .any(|m| Path::new(&home).starts_with(&m.target))
+        {
+            cmd.arg("--tmpfs");
+            cmd.arg(format!("{}:rw,mode=1777", home));
+        }
     }
+    if matches!(kind, ImageKind::Python { .. }) && !has_custom_dockerfile {
+        // Modules of the tool resolve from /app wherever the command runs
+        cmd.args(["-e", "PYTHONPATH=/app"]);
+    }
+
+    // Mounts, environment, network and extra flags from the tool's profile
+    cmd.args(profile.run_args());
 
-    // Mount volumes for tool access to configs and data
-    cmd.args([
-        "-v",
-        &format!("{}/.ssh:{}/.ssh:ro", home, home), // SSH configs (read-only)
-        "-v",
-        &format!("{}/.config:{}/.config", home, home), // App configs (read-write)
-        "-v",
-        &format!("{}:{}", home, home), // Home directory (preserves paths)
-        "-w",
-        "/app",
-    ]);
+    // Run where the user is, so relative paths in the arguments resolve
+    let cwd = std::env::current_dir().context("Cannot determine current directory")?;
+    let workdir = profile.container_path(&cwd);
+    let workdir = workdir
+        .as_deref()
+        .map(Path::to_string_lossy)
+        .unwrap_or_else(|| "/app".into());
+    cmd.args(["-w", &workdir]);
 
     // Add the image
     cmd.arg(image_name);
@@ -217,7 +1110,7 @@ fn run_container(
     // For auto-generated Dockerfiles, provide the full command
     if !has_custom_dockerfile {
         // Add the command to run (auto-generated images need full command)
-        cmd.args(container_cmd.split_whitespace());
+        cmd.args(container_command(tool_config, kind, tool_path));
     }
     // else: custom Dockerfile has ENTRYPOINT, just pass args
 
@@ -243,9 +1136,208 @@ fn run_container(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command(r#"python3 -m tool --name "two words" 'it''s' a\ b"#),
+            vec!["python3", "-m", "tool", "--name", "two words", "its", "a b"]
+        );
+        assert_eq!(split_command(r#"echo """#), vec!["echo", ""]);
+    }
+
+    #[test]
+    fn test_container_command() {
+        let mut config = ToolConfig {
+            name: "cert-checker".to_string(),
+            ..Default::default()
+        };
+        let python = ImageKind::Python {
+            version: "3.9".to_string(),
+        };
+        let tool_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            container_command(&config, &python, tool_dir.path()),
+            vec!["python3", "-m", "cert_checker"]
+        );
+
+        config.commands = Some(crate::config::Commands {
+            setup: None,
+            run: "./check.sh --all".to_string(),
+        });
+        assert_eq!(
+            container_command(&config, &ImageKind::Bash, tool_dir.path()),
+            vec!["bash", "-c", "./check.sh --all \"$@\"", "cert-checker"]
+        );
+
+        // The container starts in the user's directory, so the tool's own
+        // files are addressed under /app
+        std::fs::write(tool_dir.path().join("check.sh"), "").unwrap();
+        assert_eq!(
+            container_command(&config, &ImageKind::Bash, tool_dir.path()),
+            vec!["bash", "-c", "/app/check.sh --all \"$@\"", "cert-checker"]
+        );
+
+        // Shell syntax reaches bash untouched, as in `run_bash_script`
+        config.commands = Some(crate::config::Commands {
+            setup: None,
+            run: "./check.sh --all && echo \"$HOME\" | tee 'out log'".to_string(),
+        });
+        assert_eq!(
+            container_command(&config, &ImageKind::Bash, tool_dir.path()),
+            vec![
+                "bash",
+                "-c",
+                "/app/check.sh --all && echo \"$HOME\" | tee 'out log' \"$@\"",
+                "cert-checker"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registry_flags_allowlist() {
+        let home = Path::new("/home/alice");
+        let cwd = Path::new("/home/alice/project");
+        let flags = |flags: &[&str]| ContainerConfig {
+            flags: flags.iter().map(|f| f.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let safe = flags(&["--read-only", "--memory=512m", "--cap-drop=ALL"]);
+        assert!(ContainerProfile::resolve(Some(&safe), None, home, cwd).is_ok());
+
+        for unsafe_flag in ["--privileged", "-v", "--cap-add=SYS_ADMIN", "--memoryx=1"] {
+            let declared = flags(&[unsafe_flag]);
+            assert!(
+                ContainerProfile::resolve(Some(&declared), None, home, cwd).is_err(),
+                "{unsafe_flag} should be refused"
+            );
+        }
+
+        // The user's own config is not restricted
+        let user = flags(&["--privileged"]);
+        let profile = ContainerProfile::resolve(None, Some(&user), home, cwd).unwrap();
+        assert_eq!(profile.flags, vec!["--privileged"]);
+        assert_eq!(
+            profile.container_path(&cwd.join("src")),
+            Some(cwd.join("src"))
+        );
+        assert_eq!(profile.container_path(home), None);
+    }
+
+    #[test]
+    fn test_container_profile() {
+        let home = Path::new("/home/alice");
+        let cwd = Path::new("/home/alice/project");
+
+        let default = ContainerProfile::resolve(None, None, home, cwd).unwrap();
+        assert_eq!(
+            default.mounts,
+            vec![Mount {
+                source: cwd.to_path_buf(),
+                target: cwd.to_path_buf(),
+                read_only: false,
+            }]
+        );
+
+        let declared = ContainerConfig {
+            mounts: vec!["~/.ssh:ro".to_string(), "$PWD".to_string()],
+            env: vec!["AWS_PROFILE".to_string()],
+            network: Some("none".to_string()),
+            flags: vec![],
+        };
+        let user = ContainerConfig {
+            mounts: vec!["/srv/data:/data:ro".to_string()],
+            network: Some("host".to_string()),
+            ..Default::default()
+        };
+        let profile = ContainerProfile::resolve(Some(&declared), Some(&user), home, cwd).unwrap();
+        assert_eq!(profile.mounts.len(), 3);
+        assert_eq!(
+            profile.mounts[0].to_arg(),
+            "/home/alice/.ssh:/home/alice/.ssh:ro"
+        );
+        assert_eq!(profile.mounts[2].to_arg(), "/srv/data:/data:ro");
+        assert_eq!(profile.network.as_deref(), Some("host"));
+        assert_eq!(profile.env, vec!["AWS_PROFILE"]);
+
+        assert!(Mount::parse("relative/dir", home, cwd).is_err());
+
+        // A registry cannot mount outside $PWD/home, write outside $PWD, reach
+        // the runtime socket or forward credentials; the user config can
+        for spec in [
+            "/",
+            "/var/run/docker.sock",
+            "~",
+            "~/.ssh",
+            "~/.docker/run/docker.sock:ro",
+            "~/../bob/.ssh:ro",
+            "/srv/data:/data:ro",
+        ] {
+            let declared = ContainerConfig {
+                mounts: vec![spec.to_string()],
+                ..Default::default()
+            };
+            assert!(
+                ContainerProfile::resolve(Some(&declared), None, home, cwd).is_err(),
+                "{spec} should be refused"
+            );
+            assert!(ContainerProfile::resolve(None, Some(&declared), home, cwd).is_ok());
+        }
+        for name in ["AWS_SECRET_ACCESS_KEY", "GITHUB_TOKEN", "SSH_AUTH_SOCK"] {
+            let declared = ContainerConfig {
+                env: vec![name.to_string()],
+                ..Default::default()
+            };
+            assert!(ContainerProfile::resolve(Some(&declared), None, home, cwd).is_err());
+            assert!(ContainerProfile::resolve(None, Some(&declared), home, cwd).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_run_labels() {
+        let run = RunLabels {
+            tool: "net scan".to_string(),
+            version: "1.2.0".to_string(),
+            pid: 4242,
+            started: 1_700_000_000,
+        };
+        assert_eq!(run.container_name(), "tuxbox-net-scan-4242-1700000000");
+
+        let labels: serde_json::Map<String, serde_json::Value> = run
+            .labels()
+            .iter()
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.into()))
+            .collect();
+        assert_eq!(RunLabels::from_labels(&labels.into()), Some(run));
+
+        // Image labels alone (no pid/start time) are not a tbox run
+        let image_only = serde_json::json!({ "tuxbox.tool": "net scan" });
+        assert_eq!(RunLabels::from_labels(&image_only), None);
+    }
+
+    #[test]
+    fn test_image_inputs_from_labels() {
+        let inputs = ImageInputs {
+            tool: "foo".to_string(),
+            commit: "abc123".to_string(),
+            dockerfile_hash: "0".repeat(64),
+        };
+        let labels: serde_json::Map<String, serde_json::Value> = inputs
+            .labels()
+            .iter()
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.into()))
+            .collect();
+        assert_eq!(ImageInputs::from_labels(&labels.into()), inputs);
+
+        // Images built without labels never match
+        assert_ne!(ImageInputs::from_labels(&serde_json::Value::Null), inputs);
+    }
+
     #[test]
     fn test_image_exists_check() {
         // Just verify the function runs
-        let _ = image_exists("nonexistent-image-xyz");
+        let _ = image_exists(ContainerRuntime::Docker, "nonexistent-image-xyz");
     }
 }
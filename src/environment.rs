@@ -1,23 +1,104 @@
 //! Environment detection for choosing execution strategy
 
+use colored::Colorize;
 use std::process::Command;
 
-/// Check if Docker is available and running
-pub fn is_docker_available() -> bool {
-    // Try to run `docker version` to check if Docker daemon is available
-    let result = Command::new("docker").arg("version").output();
+/// Container CLI used for isolated execution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+    Nerdctl,
+}
+
+impl ContainerRuntime {
+    /// Auto-detection order
+    const ALL: [ContainerRuntime; 3] = [
+        ContainerRuntime::Docker,
+        ContainerRuntime::Podman,
+        ContainerRuntime::Nerdctl,
+    ];
+
+    /// Name of the CLI binary
+    pub fn name(self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+            ContainerRuntime::Nerdctl => "nerdctl",
+        }
+    }
 
-    match result {
-        Ok(output) => output.status.success(),
-        Err(_) => false,
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|runtime| runtime.name().eq_ignore_ascii_case(name.trim()))
     }
+
+    /// A new command for this runtime's CLI
+    pub fn command(self) -> Command {
+        Command::new(self.name())
+    }
+
+    /// True if the CLI is installed and can reach its daemon/service
+    pub fn is_available(self) -> bool {
+        self.command()
+            .arg("version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// True when containers run in a user namespace owned by the calling user
+    /// (rootless Podman, rootless Docker or nerdctl)
+    pub fn is_rootless(self) -> bool {
+        let format = match self {
+            ContainerRuntime::Podman => "{{.Host.Security.Rootless}}",
+            ContainerRuntime::Docker | ContainerRuntime::Nerdctl => "{{json .SecurityOptions}}",
+        };
+        self.command()
+            .args(["info", "--format", format])
+            .output()
+            .is_ok_and(|output| {
+                let info = String::from_utf8_lossy(&output.stdout);
+                output.status.success() && (info.trim() == "true" || info.contains("rootless"))
+            })
+    }
+}
+
+/// Pick the container runtime: `TUXBOX_CONTAINER_RUNTIME` (docker, podman,
+/// nerdctl or none) if set, otherwise the first available of docker, podman
+/// and nerdctl
+pub fn detect_container_runtime() -> Option<ContainerRuntime> {
+    if let Ok(requested) = std::env::var("TUXBOX_CONTAINER_RUNTIME") {
+        if requested.trim().eq_ignore_ascii_case("none") {
+            return None;
+        }
+        match ContainerRuntime::from_name(&requested) {
+            Some(runtime) if runtime.is_available() => return Some(runtime),
+            Some(runtime) => eprintln!(
+                "  {} TUXBOX_CONTAINER_RUNTIME={} is not available, running without containers",
+                "⚠".yellow(),
+                runtime.name()
+            ),
+            None => eprintln!(
+                "  {} Unknown TUXBOX_CONTAINER_RUNTIME '{}' (expected docker, podman, nerdctl or none)",
+                "⚠".yellow(),
+                requested
+            ),
+        }
+        return None;
+    }
+
+    ContainerRuntime::ALL
+        .into_iter()
+        .find(|runtime| runtime.is_available())
 }
 
 /// Detect the best execution environment
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ExecutionEnvironment {
-    /// Docker is available - use containerized execution (PREFERRED)
-    Docker,
-    /// Docker not available - use local venv (FALLBACK)
+    /// A container runtime is available - use containerized execution (PREFERRED)
+    Container(ContainerRuntime),
+    /// No container runtime available - use local venv (FALLBACK)
     LocalVenv,
 }
 
@@ -28,10 +109,9 @@ pub fn detect_environment() -> ExecutionEnvironment {
         return ExecutionEnvironment::LocalVenv;
     }
 
-    if is_docker_available() {
-        ExecutionEnvironment::Docker
-    } else {
-        ExecutionEnvironment::LocalVenv
+    match detect_container_runtime() {
+        Some(runtime) => ExecutionEnvironment::Container(runtime),
+        None => ExecutionEnvironment::LocalVenv,
     }
 }
 
@@ -44,4 +124,13 @@ mod tests {
         // Just verify the function runs without panic
         let _env = detect_environment();
     }
+
+    #[test]
+    fn test_runtime_from_name() {
+        assert_eq!(
+            ContainerRuntime::from_name("Podman"),
+            Some(ContainerRuntime::Podman)
+        );
+        assert_eq!(ContainerRuntime::from_name("lxc"), None);
+    }
 }
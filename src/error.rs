@@ -22,6 +22,18 @@ pub enum TuxBoxError {
     #[error("Update failed: {0}")]
     UpdateError(String),
 
+    #[error("HTTP error: {0}")]
+    HttpError(String),
+
+    #[error("Checksum mismatch for {0}: expected {1}, got {2}")]
+    ChecksumMismatch(String, String, String),
+
+    #[error("Signature verification failed: {0}")]
+    SignatureError(String),
+
+    #[error("Not allowed: {0}")]
+    PolicyError(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -4,9 +4,18 @@ use anyhow::Result;
 
 use crate::config::tools_dir;
 use crate::error::TuxBoxError;
+use crate::signing::{self, ToolSigning};
+use crate::tool_state::ToolState;
 
 /// Clone a Git repository to the tools directory
-pub fn clone_tool(tool_name: &str, repo_url: &str, branch: Option<&str>) -> Result<()> {
+///
+/// With `signing.require_signed`, a clone whose HEAD is not validly signed is removed again.
+pub fn clone_tool(
+    tool_name: &str,
+    repo_url: &str,
+    branch: Option<&str>,
+    signing: Option<&ToolSigning>,
+) -> Result<()> {
     let tools_dir = tools_dir()?;
     let tool_path = tools_dir.join(tool_name);
 
@@ -14,65 +23,25 @@ pub fn clone_tool(tool_name: &str, repo_url: &str, branch: Option<&str>) -> Resu
         return Ok(()); // Already cloned
     }
 
-    println!("  → Cloning {} from {}...", tool_name, repo_url);
-
-    // For SSH URLs, use system git (handles SSH keys automatically)
-    if repo_url.starts_with("git@") || repo_url.starts_with("ssh://") {
-        return clone_with_system_git(repo_url, &tool_path, branch);
-    }
-
-    // For HTTPS URLs, try git2 with proxy auto-detection first
-    let mut builder = git2::build::RepoBuilder::new();
-
-    // Auto-detect proxy from environment variables (http_proxy, https_proxy, etc.)
-    let mut fetch_options = git2::FetchOptions::new();
-    let mut proxy_opts = git2::ProxyOptions::new();
-    proxy_opts.auto();
-    fetch_options.proxy_options(proxy_opts);
-    builder.fetch_options(fetch_options);
-
-    if let Some(branch_name) = branch {
-        builder.branch(branch_name);
-    }
-
-    if builder.clone(repo_url, &tool_path).is_ok() {
-        println!("  ✓ Cloned successfully");
-        return Ok(());
+    clone_repo(tool_name, repo_url, &tool_path, branch)?;
+    if let Err(e) = verify_checkout(tool_name, &tool_path, signing) {
+        let _ = std::fs::remove_dir_all(&tool_path);
+        return Err(e);
     }
-
-    // Fallback: system git handles proxy env vars natively
-    clone_with_system_git(repo_url, &tool_path, branch)
+    Ok(())
 }
 
-/// Clone using system git command (for SSH URLs)
-fn clone_with_system_git(
+fn clone_repo(
+    tool_name: &str,
     repo_url: &str,
-    dest: &std::path::Path,
+    tool_path: &std::path::Path,
     branch: Option<&str>,
 ) -> Result<()> {
-    use std::process::Command;
-
-    let mut cmd = Command::new("git");
-    cmd.arg("clone");
-
-    if let Some(branch_name) = branch {
-        cmd.args(["--branch", branch_name]);
-    }
-
-    cmd.arg(repo_url);
-    cmd.arg(dest);
-
-    let output = cmd
-        .output()
-        .map_err(|e| TuxBoxError::GitError(format!("Failed to execute git command: {}", e)))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(TuxBoxError::GitError(format!("Git clone failed: {}", stderr.trim())).into());
-    }
+    println!("  → Cloning {} from {}...", tool_name, repo_url);
 
+    // git2 for every transport: proxy auto-detection, ssh-agent/key files, HTTPS tokens
+    crate::credentials::clone(repo_url, tool_path, branch, None)?;
     println!("  ✓ Cloned successfully");
-
     Ok(())
 }
 
@@ -89,6 +58,73 @@ pub fn tool_path(tool_name: &str) -> Result<std::path::PathBuf> {
     Ok(tools_dir()?.join(tool_name))
 }
 
+/// Full hash of the commit checked out in a tool's repository
+pub fn head_commit(tool_path: &std::path::Path) -> Result<String> {
+    let repo = git2::Repository::open(tool_path)
+        .map_err(|e| TuxBoxError::GitError(format!("Failed to open repository: {}", e)))?;
+    let head = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(|e| TuxBoxError::GitError(format!("Failed to resolve HEAD: {}", e)))?;
+    Ok(head.id().to_string())
+}
+
+/// Refuse to run a checkout whose HEAD lacks a signature required by `signing`
+pub fn verify_checkout(
+    tool_name: &str,
+    tool_path: &std::path::Path,
+    signing: Option<&ToolSigning>,
+) -> Result<()> {
+    if !signing.is_some_and(|s| s.require_signed) {
+        return Ok(());
+    }
+    let head = git2::Repository::open(tool_path)
+        .and_then(|repo| repo.head()?.peel_to_commit().map(|c| c.id()))
+        .map_err(|e| TuxBoxError::GitError(format!("Failed to resolve HEAD: {}", e)))?;
+    verify_tool_revision(tool_name, tool_path, signing, head)
+}
+
+/// Check revision `oid` of a tool against `signing` (no-op unless `require_signed`).
+/// Verified revisions are remembered, so an unchanged checkout is checked once.
+fn verify_tool_revision(
+    tool_name: &str,
+    tool_path: &std::path::Path,
+    signing: Option<&ToolSigning>,
+    oid: git2::Oid,
+) -> Result<()> {
+    use colored::Colorize;
+
+    let Some(signing) = signing.filter(|s| s.require_signed) else {
+        return Ok(());
+    };
+    if signing::verified_revision(tool_path).as_deref() == Some(oid.to_string().as_str()) {
+        return Ok(());
+    }
+
+    let repo = git2::Repository::open(tool_path)
+        .map_err(|e| TuxBoxError::GitError(format!("Failed to open repository: {}", e)))?;
+    let signer = signing::verify_revision(&repo, oid, signing).map_err(|e| {
+        let reason = e.to_string();
+        let reason = reason
+            .strip_prefix("Signature verification failed: ")
+            .unwrap_or(&reason)
+            .to_string();
+        TuxBoxError::SignatureError(format!(
+            "revision {} of '{}' is rejected: {}",
+            &oid.to_string()[..12],
+            tool_name,
+            reason
+        ))
+    })?;
+    println!(
+        "  {} {} signed by {}",
+        "✓".green(),
+        &oid.to_string()[..12],
+        signer
+    );
+    signing::record_verified(tool_path, oid)
+}
+
 /// Update a tool (git pull with safety checks)
 pub fn update_tool(tool_name: &str) -> Result<()> {
     use colored::Colorize;
@@ -100,11 +136,27 @@ pub fn update_tool(tool_name: &str) -> Result<()> {
     }
 
     // Native tools (pre-built binaries) have no git repo — delegate to native module
-    if crate::native::is_native_tool_dir(&tool_path) {
-        return crate::native::update_native_tool(tool_name);
+    let updated = if crate::native::is_native_tool_dir(&tool_path) {
+        crate::native::update_native_tool(tool_name).map(|()| true)
+    } else {
+        update_git_tool(tool_name, &tool_path)
+    };
+
+    // An explicit update lifts the pin placed by `tbox rollback`, once it went
+    // through; a failed or rejected one leaves the tool pinned where it is
+    if updated? && ToolState::is_pinned(&tool_path) {
+        ToolState::set_pinned(&tool_path, false)?;
+        println!("  {} {} — unpinned", "→".cyan(), tool_name.bold());
     }
+    Ok(())
+}
 
-    let repo = git2::Repository::open(&tool_path)
+/// Update a git checkout: clean working tree, signature policy, fast-forward.
+/// False when it was skipped because of local changes.
+fn update_git_tool(tool_name: &str, tool_path: &std::path::Path) -> Result<bool> {
+    use colored::Colorize;
+
+    let repo = git2::Repository::open(tool_path)
         .map_err(|e| TuxBoxError::GitError(format!("Failed to open repository: {}", e)))?;
 
     // Check if working directory is clean (tracked files only — excludes venv/, etc.)
@@ -127,96 +179,41 @@ pub fn update_tool(tool_name: &str) -> Result<()> {
             format!("rm -rf {}", tool_path.display()).bright_black()
         );
         eprintln!("    {}", format!("tbox run {}", tool_name).bright_black());
-        return Ok(());
+        return Ok(false);
     }
 
-    // Detect remote URL to choose fetch strategy (before moving repo)
-    let remote_url = repo
-        .find_remote("origin")
-        .ok()
-        .and_then(|r| r.url().map(|u| u.to_string()))
-        .unwrap_or_default();
-
-    let is_ssh = remote_url.starts_with("git@") || remote_url.starts_with("ssh://");
-
     // Drop statuses (borrow of repo) before consuming repo
     drop(statuses);
 
-    if is_ssh {
-        update_tool_system_git(tool_name, &tool_path)
-    } else {
-        update_tool_git2(tool_name, &tool_path, repo)
-    }
-}
-
-/// Update a tool via system `git pull --ff-only` (used for SSH remotes)
-fn update_tool_system_git(tool_name: &str, tool_path: &std::path::Path) -> Result<()> {
-    use colored::Colorize;
-    use std::process::Command;
-
-    // Record HEAD before pull to detect whether it advanced
-    let before_oid = git2::Repository::open(tool_path).ok().and_then(|r| {
-        r.head()
-            .ok()
-            .and_then(|h| h.peel_to_commit().ok())
-            .map(|c| c.id())
-    });
-
-    let output = Command::new("git")
-        .args(["pull", "--ff-only"])
-        .current_dir(tool_path)
-        .output()
-        .map_err(|e| TuxBoxError::GitError(format!("Failed to execute git pull: {}", e)))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprintln!(
-            "{}",
-            format!(
-                "  ✗ {} — update failed (non-fast-forward or conflict)",
-                tool_name
-            )
-            .red()
-        );
-        if !stderr.trim().is_empty() {
-            eprintln!("  {} {}", "→".cyan(), stderr.trim());
+    // New revisions must be signed when the registry requires it; when that
+    // cannot be determined, the update is refused rather than left unchecked
+    let signing = match crate::config::load_config() {
+        Ok(config) => crate::registry::tool_signing(
+            tool_name,
+            &config.registries,
+            &crate::config::registry_dir()?,
+        ),
+        Err(e) if matches!(e.downcast_ref(), Some(TuxBoxError::NotInitialized)) => Ok(None),
+        Err(e) => Err(e),
+    };
+    let signing = match signing {
+        Ok(signing) => signing,
+        Err(e) => {
+            let kept = repo.head().ok().and_then(|h| h.target());
+            reject_update(tool_name, kept, "signature requirements unknown");
+            return Err(e);
         }
-        eprintln!("  {} To force clean reinstall:", "→".cyan());
-        eprintln!(
-            "    {}",
-            format!("rm -rf {}", tool_path.display()).bright_black()
-        );
-        eprintln!("    {}", format!("tbox run {}", tool_name).bright_black());
-        return Err(TuxBoxError::GitError(format!("git pull failed for '{}'", tool_name)).into());
-    }
-
-    // Check if HEAD advanced
-    let after_oid = git2::Repository::open(tool_path).ok().and_then(|r| {
-        r.head()
-            .ok()
-            .and_then(|h| h.peel_to_commit().ok())
-            .map(|c| c.id())
-    });
-
-    if before_oid != after_oid {
-        println!("  {} {} — updated", "✓".green(), tool_name.bold());
-        crate::tool_state::ToolState::invalidate(tool_path);
-    } else {
-        println!(
-            "  {} {} — already up to date",
-            "✓".green(),
-            tool_name.bold()
-        );
-    }
+    };
 
-    Ok(())
+    update_tool_git2(tool_name, tool_path, repo, signing.as_ref()).map(|()| true)
 }
 
-/// Update a tool via git2 fetch + fast-forward (used for HTTPS remotes)
+/// Update a tool via git2 fetch + fast-forward
 fn update_tool_git2(
     tool_name: &str,
     tool_path: &std::path::Path,
     repo: git2::Repository,
+    signing: Option<&ToolSigning>,
 ) -> Result<()> {
     use colored::Colorize;
 
@@ -232,10 +229,10 @@ fn update_tool_git2(
         .find_remote("origin")
         .map_err(|e| TuxBoxError::GitError(format!("Remote 'origin' not found: {}", e)))?;
 
-    let mut fetch_options = git2::FetchOptions::new();
-    let mut proxy_opts = git2::ProxyOptions::new();
-    proxy_opts.auto();
-    fetch_options.proxy_options(proxy_opts);
+    let remote_url = remote.url().unwrap_or_default().to_string();
+    let mut fetch_options = crate::credentials::fetch_options(None);
+    // Signed release tags can vouch for otherwise unsigned commits
+    fetch_options.download_tags(git2::AutotagOption::All);
 
     remote
         .fetch(
@@ -243,7 +240,7 @@ fn update_tool_git2(
             Some(&mut fetch_options),
             None,
         )
-        .map_err(|e| TuxBoxError::GitError(format!("Fetch failed: {}", e)))?;
+        .map_err(|e| crate::credentials::git_error("Fetch of", &remote_url, e))?;
 
     let branch_name = head
         .shorthand()
@@ -284,6 +281,11 @@ fn update_tool_git2(
         return Ok(());
     }
 
+    if let Err(e) = verify_tool_revision(tool_name, tool_path, signing, remote_oid) {
+        reject_update(tool_name, Some(current_oid), "bad or missing signature");
+        return Err(e);
+    }
+
     let refname = format!("refs/heads/{}", branch_name);
     let mut reference = repo.find_reference(&refname).map_err(|e| {
         TuxBoxError::GitError(format!("Failed to find reference '{}': {}", refname, e))
@@ -300,7 +302,8 @@ fn update_tool_git2(
                 behind,
                 if behind == 1 { "commit" } else { "commits" }
             );
-            crate::tool_state::ToolState::invalidate(tool_path);
+            ToolState::record_previous(tool_path, &current_oid.to_string())?;
+            ToolState::invalidate(tool_path);
         }
         Err(e) => {
             eprintln!(
@@ -327,6 +330,23 @@ fn update_tool_git2(
     Ok(())
 }
 
+/// Report an update refused by signature checks; the checkout is left untouched
+fn reject_update(tool_name: &str, kept: Option<git2::Oid>, reason: &str) {
+    use colored::Colorize;
+
+    let kept = kept
+        .map(|oid| oid.to_string()[..7].to_string())
+        .unwrap_or_default();
+    eprintln!(
+        "{}",
+        format!(
+            "  ✗ {} — update rejected ({}), keeping {}",
+            tool_name, reason, kept
+        )
+        .red()
+    );
+}
+
 /// Update all installed tools
 pub fn update_all_tools() -> Result<()> {
     let tools_dir = tools_dir()?;
@@ -340,9 +360,90 @@ pub fn update_all_tools() -> Result<()> {
         let entry = entry?;
         if entry.file_type()?.is_dir() {
             let tool_name = entry.file_name().to_string_lossy().to_string();
+            if ToolState::is_pinned(&entry.path()) {
+                use colored::Colorize;
+                println!(
+                    "  {} {} — pinned after rollback, skipping (run 'tbox update {}' to unpin)",
+                    "→".yellow(),
+                    tool_name.bold(),
+                    tool_name
+                );
+                continue;
+            }
             update_tool(&tool_name)?;
         }
     }
 
     Ok(())
 }
+
+/// Roll a tool back to the revision recorded by its last successful update.
+///
+/// The tool stays pinned (skipped by `tbox update`) until it is updated explicitly.
+pub fn rollback_tool(tool_name: &str) -> Result<()> {
+    use colored::Colorize;
+
+    let tool_path = tool_path(tool_name)?;
+
+    if !tool_path.exists() {
+        return Err(TuxBoxError::ToolNotFound(tool_name.to_string()).into());
+    }
+
+    if crate::native::is_native_tool_dir(&tool_path) {
+        return crate::native::rollback_native_tool(tool_name);
+    }
+
+    let previous = ToolState::history(&tool_path)
+        .and_then(|h| h.previous)
+        .ok_or_else(|| {
+            TuxBoxError::GitError(format!(
+                "No previous revision recorded for '{}' (nothing to roll back)",
+                tool_name
+            ))
+        })?;
+
+    let repo = git2::Repository::open(&tool_path)
+        .map_err(|e| TuxBoxError::GitError(format!("Failed to open repository: {}", e)))?;
+
+    let mut status_opts = git2::StatusOptions::new();
+    status_opts.include_untracked(false);
+    status_opts.include_ignored(false);
+    let is_dirty = !repo
+        .statuses(Some(&mut status_opts))
+        .map_err(|e| TuxBoxError::GitError(format!("Failed to check repository status: {}", e)))?
+        .is_empty();
+    if is_dirty {
+        return Err(TuxBoxError::GitError(format!(
+            "'{}' has uncommitted changes, refusing to roll back",
+            tool_name
+        ))
+        .into());
+    }
+
+    let oid = git2::Oid::from_str(&previous)
+        .map_err(|e| TuxBoxError::GitError(format!("Invalid recorded revision: {}", e)))?;
+    let commit = repo.find_commit(oid).map_err(|e| {
+        TuxBoxError::GitError(format!("Previous revision {} not found: {}", previous, e))
+    })?;
+
+    // Hard reset moves the branch back, so a later `tbox update` fast-forwards again
+    repo.reset(commit.as_object(), git2::ResetType::Hard, None)
+        .map_err(|e| TuxBoxError::GitError(format!("Failed to reset to {}: {}", previous, e)))?;
+
+    ToolState::record_rollback(&tool_path)?;
+    ToolState::invalidate(&tool_path);
+
+    println!(
+        "  {} {} — rolled back to {} (pinned)",
+        "✓".green(),
+        tool_name.bold(),
+        previous[..previous.len().min(7)].green()
+    );
+    println!(
+        "  {} Run 'tbox update {}' to return to the latest version",
+        "→".dimmed(),
+        tool_name
+    );
+
+    Ok(())
+}
@@ -0,0 +1,366 @@
+//! Shared HTTP client for release metadata and downloads
+//!
+//! Every request goes through here so authentication is applied consistently.
+//! Credentials for a host are looked up in this order:
+//! 1. `TUXBOX_GITHUB_TOKEN` / `GITHUB_TOKEN` (github.com and api.github.com only)
+//! 2. `[http.tokens]` in `~/.tuxbox/config.toml`, keyed by host
+//! 3. `~/.netrc` (or `$NETRC`) `machine` entries
+//! 4. The GitHub CLI's stored token (`gh auth token --hostname <host>`), for
+//!    github.com and the GitHub Enterprise hosts listed in `[http] github_hosts`
+//!
+//! Tokens are sent as `Authorization: Bearer`; netrc entries use basic auth.
+//! The netrc `default` entry only applies to the hosts in `[http] github_hosts`,
+//! and credentials are never sent over plain `http://`.
+//! Git HTTPS remotes use the same credentials (see `credentials`).
+//! reqwest drops the header when a redirect leaves the original host.
+
+use anyhow::Result;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::TuxBoxError;
+use crate::release::ReleaseAsset;
+
+/// `[http]` section of `~/.tuxbox/config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Per-host API tokens, e.g. `"ghe.corp.example" = "ghp_..."`
+    #[serde(default)]
+    pub tokens: HashMap<String, String>,
+    /// GitHub Enterprise hosts whose token may be taken from the GitHub CLI
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub github_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Credentials {
+    Bearer(String),
+    Basic(String, String),
+}
+
+/// GET `url` and deserialize the JSON body
+pub fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T> {
+    let response = send(url, None)?;
+    response.json().map_err(|e| {
+        TuxBoxError::HttpError(format!("Failed to parse response from {}: {}", url, e)).into()
+    })
+}
+
+/// GET `url` and return the body as text
+pub fn get_text(url: &str) -> Result<String> {
+    Ok(send(url, None)?.text()?)
+}
+
+/// GET `url` and return the raw body
+pub fn download(url: &str) -> Result<Vec<u8>> {
+    Ok(send(url, None)?.bytes()?.to_vec())
+}
+
+/// Download a release asset.
+///
+/// When credentials exist for the asset's API URL (GitHub), the API endpoint is
+/// used with `Accept: application/octet-stream`, which also works for assets of
+/// private repositories where the browser URL returns 404.
+pub fn download_asset(asset: &ReleaseAsset) -> Result<Vec<u8>> {
+    if let Some(api_url) = asset
+        .api_url
+        .as_deref()
+        .filter(|u| url_credentials(u).is_some())
+    {
+        return Ok(send(api_url, Some("application/octet-stream"))?
+            .bytes()?
+            .to_vec());
+    }
+    download(&asset.download_url)
+}
+
+/// True when a HEAD request to `url` succeeds
+pub fn exists(url: &str) -> bool {
+    client()
+        .map(|c| authorize(c.head(url), url))
+        .and_then(|req| Ok(req.send()?))
+        .is_ok_and(|r| r.status().is_success())
+}
+
+fn client() -> Result<Client> {
+    Ok(Client::builder().user_agent("tuxbox").build()?)
+}
+
+fn send(url: &str, accept: Option<&str>) -> Result<Response> {
+    let host = host_of(url);
+    let mut request = authorize(client()?.get(url), url);
+    if let Some(accept) = accept {
+        request = request.header(reqwest::header::ACCEPT, accept);
+    }
+
+    let response = request
+        .send()
+        .map_err(|e| TuxBoxError::HttpError(format!("Request to {} failed: {}", url, e)))?;
+
+    check_status(response, url, &host)
+}
+
+fn authorize(request: RequestBuilder, url: &str) -> RequestBuilder {
+    match url_credentials(url) {
+        Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+        Some(Credentials::Basic(login, password)) => request.basic_auth(login, Some(password)),
+        None => request,
+    }
+}
+
+/// Turn error statuses into actionable errors (rate limits, private repos)
+fn check_status(response: Response, url: &str, host: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+
+    let rate_limited = (status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS)
+        && header("x-ratelimit-remaining").as_deref() == Some("0");
+
+    let message = if rate_limited {
+        let reset = header("x-ratelimit-reset")
+            .and_then(|r| r.parse::<u64>().ok())
+            .map(format_reset)
+            .unwrap_or_else(|| "later".to_string());
+        format!(
+            "API rate limit exceeded for {} (resets {}). \
+             Set GITHUB_TOKEN or add a token under [http.tokens] to raise the limit",
+            host, reset
+        )
+    } else if status == StatusCode::NOT_FOUND && credentials_for(host).is_none() {
+        format!(
+            "{} returned 404. If the repository is private, set GITHUB_TOKEN \
+             or add a token for '{}' under [http.tokens]",
+            url, host
+        )
+    } else {
+        format!("{} returned {}", url, status)
+    };
+
+    Err(TuxBoxError::HttpError(message).into())
+}
+
+/// "at 14:05 UTC, in 12 min" from a unix timestamp
+fn format_reset(reset: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let seconds_of_day = reset % 86_400;
+    format!(
+        "at {:02}:{:02} UTC, in {} min",
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60,
+        reset.saturating_sub(now).div_ceil(60)
+    )
+}
+
+/// Credentials for a git HTTPS remote as (username, password).
+/// Tokens go in the password; GitHub, GitLab and Gitea accept any username with them.
+pub fn git_credentials(url: &str) -> Option<(String, String)> {
+    match url_credentials(url)? {
+        Credentials::Bearer(token) => Some(("x-access-token".to_string(), token)),
+        Credentials::Basic(login, password) => Some((login, password)),
+    }
+}
+
+fn host_of(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Credentials to send with a request to `url`; never for plain `http://`
+fn url_credentials(url: &str) -> Option<Credentials> {
+    let https = reqwest::Url::parse(url).is_ok_and(|u| u.scheme() == "https");
+    if !https {
+        return None;
+    }
+    credentials_for(&host_of(url))
+}
+
+/// Resolve (and cache per process) the credentials for `host`
+fn credentials_for(host: &str) -> Option<Credentials> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Option<Credentials>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+
+    if let Some(cached) = cache.lock().ok()?.get(host) {
+        return cached.clone();
+    }
+
+    let resolved = lookup_credentials(host);
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(host.to_string(), resolved.clone());
+    }
+    resolved
+}
+
+fn lookup_credentials(host: &str) -> Option<Credentials> {
+    if host.is_empty() {
+        return None;
+    }
+
+    // Tokens are stored against the web host; api.github.com shares github.com's
+    let web_host = if host == "api.github.com" {
+        "github.com"
+    } else {
+        host
+    };
+
+    if web_host == "github.com" {
+        let env_token = ["TUXBOX_GITHUB_TOKEN", "GITHUB_TOKEN"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|t| !t.is_empty()));
+        if let Some(token) = env_token {
+            return Some(Credentials::Bearer(token));
+        }
+    }
+
+    let http_config = crate::config::load_config()
+        .ok()
+        .and_then(|c| c.http)
+        .unwrap_or_default();
+    let config_token = http_config
+        .tokens
+        .get(host)
+        .or_else(|| http_config.tokens.get(web_host));
+    if let Some(token) = config_token {
+        return Some(Credentials::Bearer(token.clone()));
+    }
+
+    // The netrc `default` entry is for hosts the user named, not whatever a
+    // registry points at
+    let configured = http_config.github_hosts.iter().any(|h| h == web_host);
+    if let Some(entry) =
+        netrc_credentials(host, configured).or_else(|| netrc_credentials(web_host, configured))
+    {
+        return Some(entry);
+    }
+
+    // Likewise, only ask gh about hosts known to be GitHub
+    if web_host != "github.com" && !configured {
+        return None;
+    }
+    gh_cli_token(web_host).map(Credentials::Bearer)
+}
+
+fn netrc_credentials(host: &str, use_default: bool) -> Option<Credentials> {
+    let path = std::env::var_os("NETRC")
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".netrc")))?;
+    let content = std::fs::read_to_string(path).ok()?;
+    parse_netrc(&content, host, use_default)
+}
+
+/// Find `login`/`password` for `host` (or, with `use_default`, the `default`
+/// entry) in netrc content
+fn parse_netrc(content: &str, host: &str, use_default: bool) -> Option<Credentials> {
+    let mut tokens = content.split_whitespace();
+    let mut matched: Option<(Option<String>, Option<String>)> = None;
+    let mut default: Option<(Option<String>, Option<String>)> = None;
+    let mut current: Option<bool> = None; // Some(true) = wanted machine, Some(false) = default
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => {
+                if matched.is_some() {
+                    break;
+                }
+                current = (tokens.next() == Some(host)).then_some(true);
+                if current.is_some() {
+                    matched = Some((None, None));
+                }
+            }
+            "default" => {
+                if matched.is_some() {
+                    break;
+                }
+                current = Some(false);
+                default = Some((None, None));
+            }
+            "login" | "password" => {
+                let value = tokens.next().map(str::to_string);
+                let entry = match current {
+                    Some(true) => matched.as_mut(),
+                    Some(false) => default.as_mut(),
+                    None => None,
+                };
+                if let Some(entry) = entry {
+                    if token == "login" {
+                        entry.0 = value;
+                    } else {
+                        entry.1 = value;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match matched.or(default.filter(|_| use_default))? {
+        (Some(login), Some(password)) => Some(Credentials::Basic(login, password)),
+        (None, Some(password)) => Some(Credentials::Bearer(password)),
+        _ => None,
+    }
+}
+
+fn gh_cli_token(host: &str) -> Option<String> {
+    let output = std::process::Command::new("gh")
+        .args(["auth", "token", "--hostname", host])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!token.is_empty()).then_some(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netrc() {
+        let netrc = "machine ghe.corp login me password s3cret\n\
+                     machine other.host password tok\n\
+                     default login anon password x";
+        assert_eq!(
+            parse_netrc(netrc, "ghe.corp", false),
+            Some(Credentials::Basic("me".into(), "s3cret".into()))
+        );
+        assert_eq!(
+            parse_netrc(netrc, "other.host", false),
+            Some(Credentials::Bearer("tok".into()))
+        );
+        // `default` is not handed to arbitrary hosts
+        assert_eq!(parse_netrc(netrc, "unknown.host", false), None);
+        assert_eq!(
+            parse_netrc(netrc, "unknown.host", true),
+            Some(Credentials::Basic("anon".into(), "x".into()))
+        );
+        assert_eq!(parse_netrc("machine a login b", "a", true), None);
+    }
+
+    #[test]
+    fn test_host_of() {
+        assert_eq!(
+            host_of("https://api.github.com/repos/a/b"),
+            "api.github.com"
+        );
+        assert_eq!(host_of("not a url"), "");
+    }
+}
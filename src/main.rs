@@ -3,28 +3,50 @@
 //! # Architecture
 //!
 //! TuxBox is organized into modules:
+//! - `archive`: Archive extraction for release assets (.tar.gz, .zip)
+//! - `bundle`: Offline bundles (Python wheelhouses, export/import archives)
+//! - `build`: Build-from-source tool types (cargo, go, make)
+//! - `checksum`: SHA-256 verification of downloaded release assets
 //! - `cli`: Command-line interface definitions (Clap)
 //! - `config`: Configuration management (TOML parsing, context struct)
+//! - `credentials`: git2 credential callbacks (ssh-agent, SSH keys, HTTPS tokens)
 //! - `git`: Git operations (clone, pull, status)
 //! - `runner`: Tool execution logic
 //! - `error`: Custom error types
-//! - `environment`: Environment detection (Docker availability)
-//! - `docker`: Docker container management
+//! - `environment`: Environment detection (container runtime: docker, podman, nerdctl)
+//! - `http`: Shared authenticated HTTP client for release downloads
+//! - `docker`: Container image and run management
+//! - `node`: Node.js tools (npm/pnpm/yarn installs into node_modules)
 //! - `python`: Python venv management (fallback)
 //! - `registry`: Registry management and tool resolution
+//! - `release`: Release providers (GitHub/GHE, Gitea, GitLab, plain HTTP)
+//! - `sandbox`: Sandbox for non-container runs (bubblewrap, Landlock + seccomp)
+//! - `signing`: SSH commit signature and minisign checks for registries
+//! - `trust`: Trust store (first-run confirmation) and admin source policy
 
+mod archive;
+mod build;
+mod bundle;
+mod checksum;
 mod cli;
 mod config;
+mod credentials;
 mod docker;
 mod environment;
 mod error;
 mod git;
+mod http;
 mod native;
+mod node;
 mod python;
 mod registry;
+mod release;
 mod runner;
+mod sandbox;
 mod selfupdate;
+mod signing;
 mod tool_state;
+mod trust;
 
 use anyhow::Result;
 use clap::Parser;
@@ -62,9 +84,55 @@ fn main() -> Result<()> {
                 git::update_all_tools()?;
             }
         }
+        cli::Commands::Rollback { tool } => {
+            println!("{} Rolling back tool: {}", "→".cyan(), tool.bold());
+            git::rollback_tool(&tool)?;
+        }
+        cli::Commands::Rebuild { tool, no_cache } => {
+            println!("{} Rebuilding image for: {}", "→".cyan(), tool.bold());
+            runner::rebuild_tool(&tool, no_cache)?;
+        }
         cli::Commands::Status => {
             config::show_status()?;
         }
+        cli::Commands::Ps => {
+            docker::list_containers(require_container_runtime()?)?;
+        }
+        cli::Commands::Kill { tool } => {
+            println!("{} Stopping containers of: {}", "→".cyan(), tool.bold());
+            docker::stop_containers(require_container_runtime()?, &tool)?;
+        }
+        cli::Commands::Info { tool } => {
+            config::show_tool_info(&tool)?;
+        }
+        cli::Commands::Trust { action } => match action {
+            cli::TrustAction::List => trust::list()?,
+            cli::TrustAction::Registry { name } => trust::trust_registry(&name)?,
+            cli::TrustAction::Revoke { name } => trust::revoke(&name)?,
+        },
+        cli::Commands::Bundle { action } => match action {
+            cli::BundleAction::Python { tool } => {
+                println!("{} Bundling wheels for: {}", "→".cyan(), tool.bold());
+                bundle::bundle_python(&tool)?;
+            }
+            cli::BundleAction::Export {
+                tools,
+                output,
+                images,
+            } => {
+                println!("{} Exporting bundle...", "→".cyan());
+                bundle::export(&tools, &output, images)?;
+            }
+            cli::BundleAction::Import { archive } => {
+                println!(
+                    "{} Importing bundle: {}",
+                    "→".cyan(),
+                    archive.display().to_string().bold()
+                );
+                bundle::import(&archive)?;
+                println!("{} Bundle imported successfully!", "✓".green());
+            }
+        },
         cli::Commands::Registry { action } => match action {
             cli::RegistryAction::List => {
                 config::list_registries()?;
@@ -98,13 +166,55 @@ fn main() -> Result<()> {
                 );
                 config::set_registry_priority(&name, priority)?;
             }
+            cli::RegistryAction::SetSigning {
+                name,
+                allowed_signers,
+                minisign_keys,
+                off,
+            } => {
+                let signing = if off {
+                    None
+                } else if allowed_signers.is_none() && minisign_keys.is_empty() {
+                    anyhow::bail!("Pass --allowed-signers and/or --minisign-key (or --off)");
+                } else {
+                    Some(config::RegistrySigning {
+                        allowed_signers,
+                        minisign_keys,
+                    })
+                };
+                config::set_registry_signing(&name, signing)?;
+            }
+            cli::RegistryAction::SetAuth {
+                name,
+                ssh_key,
+                token_env,
+                username,
+                off,
+            } => {
+                let auth = if off {
+                    None
+                } else if ssh_key.is_none() && token_env.is_none() && username.is_none() {
+                    anyhow::bail!("Pass --ssh-key, --token-env and/or --username (or --off)");
+                } else {
+                    Some(config::RegistryAuth {
+                        ssh_key,
+                        token_env,
+                        username,
+                    })
+                };
+                config::set_registry_auth(&name, auth)?;
+            }
             cli::RegistryAction::Sync => {
                 println!("{} Syncing all registries...", "→".cyan());
                 registry::sync_all_registries()?;
             }
         },
-        cli::Commands::SelfUpdate { install } => {
-            selfupdate::check_for_update(install)?;
+        cli::Commands::SelfUpdate { install, rollback } => {
+            if rollback {
+                selfupdate::rollback_update()?;
+            } else {
+                selfupdate::check_for_update(install)?;
+            }
         }
         cli::Commands::Version => {
             selfupdate::show_version()?;
@@ -113,3 +223,13 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// The container runtime for `ps`/`kill`, or an error when there is none
+fn require_container_runtime() -> Result<environment::ContainerRuntime> {
+    environment::detect_container_runtime().ok_or_else(|| {
+        error::TuxBoxError::ExecutionError(
+            "No container runtime available (docker, podman or nerdctl)".into(),
+        )
+        .into()
+    })
+}
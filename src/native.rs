@@ -1,62 +1,141 @@
 //! Native binary tool support
 //!
-//! Downloads pre-built binaries from GitHub releases and executes them directly.
-//! No Docker, no Python, no compilation required.
+//! Downloads pre-built binaries from releases and executes them directly.
+//! No Docker, no Python, no compilation required. Releases come from GitHub
+//! by default, or from the provider set in the registry's `release` table
+//! (see `release.rs`).
 //!
-//! Asset naming convention expected: `{tool_name}-{os}-{arch}`
-//!   e.g. `hfs-linux-x86_64`, `hfs-macos-arm64`
+//! Default asset naming convention: `{tool_name}-{os}-{arch}`
+//!   e.g. `hfs-linux-x86_64`, `hfs-linux-arm64`, `hfs-macos-arm64`
+//!
+//! Registries can override it with `[tools.<name>.native]`:
+//! - `assets`: asset name patterns tried in order. Placeholders: `{name}`,
+//!   `{tag}` (`v1.2.3`), `{version}` (`1.2.3`), `{platform}` (`linux-x86_64`),
+//!   `{os}` (`linux`/`macos`), `{goos}` (`linux`/`darwin`), `{Os}` (`Linux`/`Darwin`),
+//!   `{arch}` (`x86_64`/`aarch64`), `{goarch}` (`amd64`/`arm64`) and `{target}`
+//!   (Rust triple; on Linux musl is tried before gnu).
+//! - `binaries`: executables to take from a `.tar.gz`/`.zip` asset (same
+//!   placeholders). The first is installed as the tool's main binary, the
+//!   others under their own file names.
+//!
+//! Downloads are verified against registry `sha256` pins and any published
+//! `<asset>.sha256` / `SHA256SUMS` file before being installed.
+//!
+//! `commands.run` is a run template: `{bin}` is the main binary, `{dir}` the
+//! install directory, and a bare first word naming an installed file runs that
+//! file (default: `{bin}`). `native.entry_points` maps names to further
+//! templates, run as `tbox run <tool>:<entry>`. Installing a tool writes
+//! launcher shims for it and each entry point (`<tool>-<entry>`) to `~/.tuxbox/bin`.
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::config::{ToolConfig, tools_dir};
+use crate::archive;
+use crate::checksum;
+use crate::config::{ToolConfig, shims_dir, tools_dir};
 use crate::error::TuxBoxError;
+use crate::http;
+use crate::release::{Release, ReleaseAsset, ReleaseSelector, ReleaseSource};
+use crate::tool_state::ToolState;
 
 const VERSION_FILE: &str = ".tuxbox-native-version";
 
-/// Run a native binary tool (download from GitHub releases if not present, then execute)
-pub fn run_native_tool(tool_config: &ToolConfig, args: &[String]) -> Result<()> {
+/// `[tools.<name>.native]` — how to find and unpack a tool's release assets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NativeConfig {
+    /// Asset name patterns, tried in order (see module docs for placeholders)
+    #[serde(default)]
+    pub assets: Vec<String>,
+    /// Executables to extract from an archive asset; the first is the main binary
+    #[serde(default)]
+    pub binaries: Vec<String>,
+    /// Named run templates, runnable as `tbox run <tool>:<entry>`
+    #[serde(default)]
+    pub entry_points: HashMap<String, String>,
+}
+
+/// Run a native binary tool (download from releases if not present, then execute)
+///
+/// `entry` selects one of the registry's `native.entry_points` instead of `commands.run`.
+pub fn run_native_tool(
+    tool_config: &ToolConfig,
+    entry: Option<&str>,
+    args: &[String],
+) -> Result<()> {
     let tool_dir = tools_dir()?.join(&tool_config.name);
     let binary_path = tool_dir.join(&tool_config.name);
 
+    let template = match entry {
+        Some(entry) => tool_config
+            .native
+            .as_ref()
+            .and_then(|n| n.entry_points.get(entry))
+            .map(String::as_str)
+            .ok_or_else(|| TuxBoxError::ToolNotFound(format!("{}:{}", tool_config.name, entry)))?,
+        None => tool_config
+            .commands
+            .as_ref()
+            .map(|c| c.run.as_str())
+            .unwrap_or("{bin}"),
+    };
+
     if !binary_path.exists() {
         println!(
             "  {} Downloading native binary for {}...",
             "→".cyan(),
             tool_config.name.bold()
         );
-        download_binary(tool_config, &tool_dir, &binary_path)?;
+        download_binary(tool_config, &tool_dir)?;
     } else {
         // Ensure execute bit is set (may be missing if a previous download was interrupted)
         ensure_executable(&binary_path)?;
     }
 
-    run_binary(&binary_path, args)
+    let mut command = run_command(template, &tool_dir, &binary_path);
+    if command.is_empty() {
+        return Err(TuxBoxError::ConfigError(format!(
+            "Empty run command for '{}'",
+            tool_config.name
+        ))
+        .into());
+    }
+    let program = command.remove(0);
+    command.extend_from_slice(args);
+    run_binary(tool_config, &tool_dir, Path::new(&program), &command)
 }
 
-/// Re-download binary if a newer release is available on GitHub
+/// Re-download binary if a newer release is available
 pub fn update_native_tool(tool_name: &str) -> Result<()> {
     let tool_dir = tools_dir()?.join(tool_name);
-    let binary_path = tool_dir.join(tool_name);
     let version_file = tool_dir.join(VERSION_FILE);
 
     let installed_tag = fs::read_to_string(&version_file)
         .map(|s| s.trim().to_string())
         .unwrap_or_default();
 
-    // We need the repo URL — read it from the version file's sibling or re-fetch via registry.
-    // Simplest: always fetch latest release and compare tags.
-    let repo_url = read_repo_url(&tool_dir)?;
-    let (owner, repo) = parse_github_url(&repo_url)?;
-    let api_url = format!(
-        "https://api.github.com/repos/{}/{}/releases/latest",
-        owner, repo
-    );
+    // Prefer the registry definition (release source, checksum pins, ...),
+    // approved like any other run; fall back to the repo URL stored at install
+    // time only for tools no registry defines.
+    let tool_config = match crate::registry::cached_tool_config(tool_name)? {
+        Some((config, registry)) => {
+            crate::trust::confirm_tool(&config, &registry)?;
+            config
+        }
+        None => ToolConfig {
+            name: tool_name.to_string(),
+            repo: read_repo_url(&tool_dir)?,
+            tool_type: Some("native".to_string()),
+            ..Default::default()
+        },
+    };
 
-    let release = fetch_release(&api_url)?;
+    // Resolve the release the registry asks for (latest, tag or range) and compare tags
+    let release = release_source(&tool_config)
+        .resolve(&tool_config.repo, &release_selector(&tool_config)?)?;
 
     if release.tag_name == installed_tag {
         println!(
@@ -76,19 +155,60 @@ pub fn update_native_tool(tool_name: &str) -> Result<()> {
         release.tag_name.green()
     );
 
-    // Re-create a temporary ToolConfig with just what we need
-    let tool_config = ToolConfig {
-        name: tool_name.to_string(),
-        repo: repo_url,
-        branch: None,
-        version: None,
-        tool_type: Some("native".to_string()),
-        isolation: None,
-        commands: None,
-        python_version: None,
+    install_release(&tool_config, &release, &tool_dir, true)?;
+
+    if previous_binary_path(&tool_dir, tool_name).exists() && !installed_tag.is_empty() {
+        ToolState::record_previous(&tool_dir, &installed_tag)?;
+    }
+    Ok(())
+}
+
+/// Restore the binary that was installed before the last update
+pub fn rollback_native_tool(tool_name: &str) -> Result<()> {
+    let tool_dir = native_tool_dir(tool_name)?
+        .ok_or_else(|| TuxBoxError::ToolNotFound(tool_name.to_string()))?;
+    let prev_binary = previous_binary_path(&tool_dir, tool_name);
+
+    let previous_tag = ToolState::history(&tool_dir)
+        .and_then(|h| h.previous)
+        .filter(|_| prev_binary.exists())
+        .ok_or_else(|| {
+            TuxBoxError::ExecutionError(format!(
+                "No previous binary recorded for '{}' (nothing to roll back)",
+                tool_name
+            ))
+        })?;
+
+    // Only the binaries the tool installs; other `.prev` files are not ours
+    let tool_config = match crate::registry::cached_tool_config(tool_name)? {
+        Some((config, _)) => config,
+        None => ToolConfig {
+            name: tool_name.to_string(),
+            ..Default::default()
+        },
     };
+    for name in installed_names(&tool_config) {
+        let prev = previous_binary_path(&tool_dir, &name);
+        if prev.exists() {
+            let restored = tool_dir.join(&name);
+            fs::rename(&prev, &restored).context("Failed to restore previous binary")?;
+            ensure_executable(&restored)?;
+        }
+    }
+    fs::write(tool_dir.join(VERSION_FILE), &previous_tag)?;
+    ToolState::record_rollback(&tool_dir)?;
 
-    download_binary(&tool_config, &tool_dir, &binary_path)?;
+    println!(
+        "  {} {} — rolled back to {} (pinned)",
+        "✓".green(),
+        tool_name.bold(),
+        previous_tag.green()
+    );
+    println!(
+        "  {} Run 'tbox update {}' to return to the latest version",
+        "→".dimmed(),
+        tool_name
+    );
     Ok(())
 }
 
@@ -99,29 +219,66 @@ pub fn is_native_tool_dir(tool_dir: &Path) -> bool {
 
 // ─── Internal ───────────────────────────────────────────────────────────────
 
-fn download_binary(tool_config: &ToolConfig, tool_dir: &Path, binary_path: &Path) -> Result<()> {
-    let (owner, repo) = parse_github_url(&tool_config.repo)?;
-    let api_url = format!(
-        "https://api.github.com/repos/{}/{}/releases/latest",
-        owner, repo
-    );
+fn release_source(tool_config: &ToolConfig) -> ReleaseSource {
+    tool_config.release.clone().unwrap_or_default()
+}
 
+/// Which release to install: `tag` wins, then `version` as a semver requirement
+/// (`"1.4"` means `^1.4`, `"=1.4.2"` holds an exact version), else the latest.
+fn release_selector(tool_config: &ToolConfig) -> Result<ReleaseSelector> {
+    let prerelease = tool_config.prerelease.unwrap_or(false);
+
+    if let Some(tag) = &tool_config.tag {
+        return Ok(ReleaseSelector::Tag(tag.clone()));
+    }
+
+    match tool_config.version.as_deref().map(str::trim) {
+        None | Some("") | Some("latest") | Some("*") => Ok(ReleaseSelector::Latest { prerelease }),
+        Some(version) => {
+            let req = semver::VersionReq::parse(version.trim_start_matches('v')).map_err(|e| {
+                TuxBoxError::ConfigError(format!(
+                    "Invalid version constraint '{}' for '{}': {}",
+                    version, tool_config.name, e
+                ))
+            })?;
+            Ok(ReleaseSelector::Range { req, prerelease })
+        }
+    }
+}
+
+fn download_binary(tool_config: &ToolConfig, tool_dir: &Path) -> Result<()> {
     println!("  {} Fetching release info...", "→".cyan());
-    let release = fetch_release(&api_url)?;
+    let release =
+        release_source(tool_config).resolve(&tool_config.repo, &release_selector(tool_config)?)?;
 
-    let asset_name = get_platform_asset_name(&tool_config.name)?;
-    println!("  {} Platform: {}", "→".cyan(), asset_name.dimmed());
+    install_release(tool_config, &release, tool_dir, false)
+}
 
-    let asset = release
-        .assets
+/// Download, verify and install `release`. With `keep_previous`, the binaries
+/// being replaced are kept as `<name>.prev` for `tbox rollback`.
+fn install_release(
+    tool_config: &ToolConfig,
+    release: &Release,
+    tool_dir: &Path,
+    keep_previous: bool,
+) -> Result<()> {
+    let platform = current_platform()?;
+    println!("  {} Platform: {}", "→".cyan(), platform.suffix.dimmed());
+
+    let native = tool_config.native.clone().unwrap_or_default();
+    let candidates =
+        platform.asset_candidates(&native.assets, &tool_config.name, &release.tag_name);
+
+    let (asset, target) = candidates
         .iter()
-        .find(|a| a.name == asset_name)
+        .find_map(|(name, target)| release.asset(name).map(|a| (a, *target)))
         .ok_or_else(|| {
-            let available: Vec<&str> = release.assets.iter().map(|a| a.name.as_str()).collect();
+            let tried: Vec<&str> = candidates.iter().map(|(n, _)| n.as_str()).collect();
             TuxBoxError::ExecutionError(format!(
-                "No binary found for platform: {}\nAvailable assets: {}",
-                asset_name,
-                available.join(", ")
+                "No binary found for platform: {}\nTried: {}\nAvailable assets: {}",
+                platform.suffix,
+                tried.join(", "),
+                release.asset_names().join(", ")
             ))
         })?;
 
@@ -134,30 +291,239 @@ fn download_binary(tool_config: &ToolConfig, tool_dir: &Path, binary_path: &Path
 
     fs::create_dir_all(tool_dir).context("Failed to create tool directory")?;
 
-    let data = download_asset(&asset.browser_download_url)?;
-    fs::write(binary_path, &data).context("Failed to write binary")?;
+    let data = download_asset(&asset)?;
+    verify_asset(tool_config, release, platform.suffix, &asset.name, &data)?;
+
+    let binaries = if archive::is_archive(&asset.name) {
+        println!("  {} Extracting {}...", "→".cyan(), asset.name);
+        let wanted: Vec<String> = if native.binaries.is_empty() {
+            vec![tool_config.name.clone()]
+        } else {
+            native
+                .binaries
+                .iter()
+                .map(|b| platform.render(b, &tool_config.name, &release.tag_name, target))
+                .collect()
+        };
+        archive::extract_files(&asset.name, &data, &wanted)?
+    } else if native.binaries.len() > 1 {
+        anyhow::bail!(
+            "{} is not an archive, but {} binaries are configured",
+            asset.name,
+            native.binaries.len()
+        );
+    } else {
+        vec![data]
+    };
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(binary_path)?.permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(binary_path, perms)?;
+    // Only now that the download is verified: a failed update keeps the old backup
+    if keep_previous {
+        // Backups from an older update may cover binaries no longer installed
+        for entry in fs::read_dir(tool_dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "prev") {
+                fs::remove_file(&path).context("Failed to remove old backup")?;
+            }
+        }
+        for name in installed_names(tool_config) {
+            let binary = tool_dir.join(&name);
+            if binary.exists() {
+                fs::copy(&binary, previous_binary_path(tool_dir, &name))
+                    .context("Failed to back up current binary")?;
+            }
+        }
+    }
+
+    // Write next to the target and rename only once verified, so an interrupted
+    // or rejected download never leaves a half-written executable behind
+    for (name, contents) in installed_names(tool_config).iter().zip(&binaries) {
+        let temp_path = tool_dir.join(format!(".{}.download", name));
+        fs::write(&temp_path, contents).context("Failed to write binary")?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&temp_path)?.permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&temp_path, perms)?;
+        }
+
+        fs::rename(&temp_path, tool_dir.join(name)).context("Failed to install binary")?;
     }
 
     // Persist the repo URL and installed tag for future updates
     fs::write(tool_dir.join(VERSION_FILE), &release.tag_name)?;
     fs::write(tool_dir.join(".tuxbox-native-repo"), &tool_config.repo)?;
 
+    if let Err(e) = write_shims(tool_config) {
+        println!("  {} Could not write shims: {}", "⚠".yellow(), e);
+    }
+
     println!(
         "  {} {} installed ({})",
         "✓".green(),
-        asset_name.bold(),
+        asset.name.bold(),
         release.tag_name.green()
     );
     Ok(())
 }
 
+/// Expand a run template into program + leading arguments.
+///
+/// `{bin}` and `{dir}` are substituted; a first word without a path separator
+/// that names a file in the tool directory (e.g. the tool name) is resolved to it.
+fn run_command(template: &str, tool_dir: &Path, binary_path: &Path) -> Vec<String> {
+    let mut parts: Vec<String> = template
+        .split_whitespace()
+        .map(|part| {
+            part.replace("{bin}", &binary_path.to_string_lossy())
+                .replace("{dir}", &tool_dir.to_string_lossy())
+        })
+        .collect();
+
+    if let Some(first) = parts.first_mut()
+        && !first.contains('/')
+        && tool_dir.join(first.as_str()).is_file()
+    {
+        *first = tool_dir.join(first.as_str()).to_string_lossy().to_string();
+    }
+    parts
+}
+
+/// Write `~/.tuxbox/bin` launchers that re-enter `tbox run` for the tool and
+/// each of its entry points
+pub fn write_shims(tool_config: &ToolConfig) -> Result<()> {
+    let dir = shims_dir()?;
+    fs::create_dir_all(&dir)?;
+    let tbox = std::env::current_exe()?;
+
+    let entries = tool_config
+        .native
+        .iter()
+        .flat_map(|n| n.entry_points.keys())
+        .map(|entry| {
+            (
+                format!("{}-{}", tool_config.name, entry),
+                format!("{}:{}", tool_config.name, entry),
+            )
+        });
+    let shims =
+        std::iter::once((tool_config.name.clone(), tool_config.name.clone())).chain(entries);
+
+    for (file_name, target) in shims {
+        crate::config::check_name("launcher", &file_name)?;
+        let path = dir.join(&file_name);
+        fs::write(&path, shim_script(&tbox, &target))?;
+        ensure_executable(&path)?;
+    }
+
+    let on_path = std::env::var_os("PATH")
+        .is_some_and(|p| std::env::split_paths(&p).any(|entry| entry == dir));
+    if !on_path {
+        println!(
+            "  {} Add {} to PATH to launch native tools directly",
+            "→".dimmed(),
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// `/bin/sh` launcher running `tbox run <target>` with the caller's arguments
+fn shim_script(tbox: &Path, target: &str) -> String {
+    format!(
+        "#!/bin/sh\n# Generated by tuxbox\nexec {} run {} \"$@\"\n",
+        shell_quote(&tbox.to_string_lossy()),
+        shell_quote(target)
+    )
+}
+
+/// Single-quote `word` for a POSIX shell
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+/// File names the tool's binaries are installed under: the tool name for the
+/// main binary, then the file name of each additional configured binary
+fn installed_names(tool_config: &ToolConfig) -> Vec<String> {
+    let extra = tool_config
+        .native
+        .iter()
+        .flat_map(|n| n.binaries.iter().skip(1))
+        .map(|b| b.rsplit('/').next().unwrap_or(b).to_string());
+    std::iter::once(tool_config.name.clone())
+        .chain(extra)
+        .collect()
+}
+
+/// Check the downloaded asset against the registry pin and any published checksum.
+///
+/// Verification is mandatory whenever a checksum source exists; a mismatch
+/// aborts the install before anything is written to the tool directory.
+fn verify_asset(
+    tool_config: &ToolConfig,
+    release: &Release,
+    platform: &str,
+    asset_name: &str,
+    data: &[u8],
+) -> Result<()> {
+    let mut verified = false;
+
+    let pinned = tool_config
+        .sha256
+        .as_ref()
+        .and_then(|pins| pins.get(platform));
+    if let Some(expected) = pinned {
+        checksum::verify(data, expected, asset_name)?;
+        println!("  {} Checksum matches registry pin", "✓".green());
+        verified = true;
+    }
+
+    if let Some(expected) = published_checksum(release, asset_name)? {
+        checksum::verify(data, &expected, asset_name)?;
+        println!("  {} Checksum matches published SHA-256", "✓".green());
+        verified = true;
+    }
+
+    if !verified {
+        println!(
+            "  {} No checksum published for {}, skipping verification",
+            "⚠".yellow(),
+            asset_name
+        );
+    }
+    Ok(())
+}
+
+/// Find the expected digest in `<asset>.sha256` or a SHA256SUMS-style release asset
+fn published_checksum(release: &Release, asset_name: &str) -> Result<Option<String>> {
+    let checksum_asset = release
+        .asset(&format!("{}.sha256", asset_name))
+        .or_else(|| {
+            checksum::SUMS_FILE_NAMES
+                .iter()
+                .find_map(|sums| release.asset(sums))
+        });
+
+    let Some(checksum_asset) = checksum_asset else {
+        return Ok(None);
+    };
+
+    let content = download_asset(&checksum_asset)?;
+    let expected = checksum::expected_digest(&String::from_utf8_lossy(&content), asset_name)
+        .ok_or_else(|| {
+            TuxBoxError::ExecutionError(format!(
+                "{} does not list a checksum for {}",
+                checksum_asset.name, asset_name
+            ))
+        })?;
+    Ok(Some(expected))
+}
+
+fn previous_binary_path(tool_dir: &Path, tool_name: &str) -> PathBuf {
+    tool_dir.join(format!("{}.prev", tool_name))
+}
+
 fn ensure_executable(binary_path: &Path) -> Result<()> {
     #[cfg(unix)]
     {
@@ -173,9 +539,15 @@ fn ensure_executable(binary_path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn run_binary(binary_path: &Path, args: &[String]) -> Result<()> {
-    let status = std::process::Command::new(binary_path)
-        .args(args)
+fn run_binary(
+    tool_config: &ToolConfig,
+    tool_dir: &Path,
+    binary_path: &Path,
+    args: &[String],
+) -> Result<()> {
+    let mut cmd = std::process::Command::new(binary_path);
+    cmd.args(args);
+    let status = crate::sandbox::wrap(tool_config, cmd, &[tool_dir])?
         .status()
         .map_err(|e| TuxBoxError::ExecutionError(format!("Failed to execute binary: {}", e)))?;
 
@@ -197,82 +569,110 @@ fn read_repo_url(tool_dir: &Path) -> Result<String> {
         .with_context(|| format!("Cannot read repo URL from {}", repo_file.display()))
 }
 
-fn parse_github_url(url: &str) -> Result<(String, String)> {
-    let url = url.trim_end_matches(".git");
-    let path = url
-        .trim_start_matches("https://github.com/")
-        .trim_start_matches("git@github.com:");
-    let parts: Vec<&str> = path.splitn(2, '/').collect();
-    if parts.len() != 2 || parts[0].is_empty() || parts[1].is_empty() {
-        anyhow::bail!("Cannot parse GitHub URL: {}", url);
-    }
-    Ok((parts[0].to_string(), parts[1].to_string()))
+/// Naming variants of the current OS/architecture used to match release assets
+struct Platform {
+    /// Default asset suffix and checksum-pin key, e.g. "linux-x86_64"
+    suffix: &'static str,
+    os: &'static str,
+    goos: &'static str,
+    os_title: &'static str,
+    arch: &'static str,
+    goarch: &'static str,
+    /// Rust target triples in order of preference (static musl first)
+    targets: &'static [&'static str],
 }
 
-fn get_platform_asset_name(tool_name: &str) -> Result<String> {
+fn current_platform() -> Result<Platform> {
     let os = std::env::consts::OS;
     let arch = std::env::consts::ARCH;
 
-    let suffix = match (os, arch) {
-        ("linux", "x86_64") => "linux-x86_64",
-        ("macos", "x86_64") => "macos-x86_64",
-        ("macos", "aarch64") => "macos-arm64",
+    let (suffix, goarch, targets): (_, _, &'static [&'static str]) = match (os, arch) {
+        ("linux", "x86_64") => (
+            "linux-x86_64",
+            "amd64",
+            &["x86_64-unknown-linux-musl", "x86_64-unknown-linux-gnu"],
+        ),
+        ("linux", "aarch64") => (
+            "linux-arm64",
+            "arm64",
+            &["aarch64-unknown-linux-musl", "aarch64-unknown-linux-gnu"],
+        ),
+        ("macos", "x86_64") => ("macos-x86_64", "amd64", &["x86_64-apple-darwin"]),
+        ("macos", "aarch64") => ("macos-arm64", "arm64", &["aarch64-apple-darwin"]),
         _ => anyhow::bail!("Unsupported platform: {} {}", os, arch),
     };
 
-    Ok(format!("{}-{}", tool_name, suffix))
-}
-
-fn fetch_release(api_url: &str) -> Result<GithubRelease> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("tuxbox")
-        .build()?;
-
-    let response = client
-        .get(api_url)
-        .send()
-        .context("Failed to fetch release from GitHub")?;
-
-    if !response.status().is_success() {
-        anyhow::bail!("GitHub API returned error: {}", response.status());
-    }
+    let (goos, os_title) = if os == "macos" {
+        ("darwin", "Darwin")
+    } else {
+        ("linux", "Linux")
+    };
 
-    response
-        .json()
-        .context("Failed to parse GitHub API response")
+    Ok(Platform {
+        suffix,
+        os,
+        goos,
+        os_title,
+        arch,
+        goarch,
+        targets,
+    })
 }
 
-fn download_asset(url: &str) -> Result<Vec<u8>> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("tuxbox")
-        .build()?;
-
-    let response = client
-        .get(url)
-        .send()
-        .context("Failed to download binary")?;
+impl Platform {
+    /// Expand asset patterns into concrete names, each with the target triple
+    /// it was rendered for. Patterns using `{target}` expand once per triple.
+    fn asset_candidates(
+        &self,
+        patterns: &[String],
+        name: &str,
+        tag: &str,
+    ) -> Vec<(String, &'static str)> {
+        let default = ["{name}-{platform}".to_string()];
+        let patterns = if patterns.is_empty() {
+            &default[..]
+        } else {
+            patterns
+        };
+
+        let mut candidates = Vec::new();
+        for pattern in patterns {
+            let targets = if pattern.contains("{target}") {
+                self.targets
+            } else {
+                &self.targets[..1]
+            };
+            for target in targets {
+                let candidate = self.render(pattern, name, tag, target);
+                if !candidates.iter().any(|(c, _)| *c == candidate) {
+                    candidates.push((candidate, *target));
+                }
+            }
+        }
+        candidates
+    }
 
-    if !response.status().is_success() {
-        anyhow::bail!("Download failed: {}", response.status());
+    fn render(&self, pattern: &str, name: &str, tag: &str, target: &str) -> String {
+        pattern
+            .replace("{name}", name)
+            .replace("{tag}", tag)
+            .replace("{version}", tag.strip_prefix('v').unwrap_or(tag))
+            .replace("{platform}", self.suffix)
+            .replace("{os}", self.os)
+            .replace("{goos}", self.goos)
+            .replace("{Os}", self.os_title)
+            .replace("{arch}", self.arch)
+            .replace("{goarch}", self.goarch)
+            .replace("{target}", target)
     }
+}
 
-    let bytes = response.bytes()?.to_vec();
+fn download_asset(asset: &ReleaseAsset) -> Result<Vec<u8>> {
+    let bytes = http::download_asset(asset).context("Failed to download binary")?;
     println!("  {} Downloaded {} bytes", "✓".green(), bytes.len());
     Ok(bytes)
 }
 
-#[derive(Debug, Deserialize)]
-struct GithubRelease {
-    tag_name: String,
-    assets: Vec<GithubAsset>,
-}
-
-#[derive(Debug, Deserialize)]
-struct GithubAsset {
-    name: String,
-    browser_download_url: String,
-}
-
 /// Return the tool directory path if the native tool is installed, or None
 pub fn native_tool_dir(tool_name: &str) -> Result<Option<PathBuf>> {
     let dir = tools_dir()?.join(tool_name);
@@ -282,3 +682,115 @@ pub fn native_tool_dir(tool_name: &str) -> Result<Option<PathBuf>> {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shim_script_quotes() {
+        let script = shim_script(Path::new("/opt/it's here/tbox"), "tool:x; rm -rf ~");
+        assert!(script.ends_with("exec '/opt/it'\\''s here/tbox' run 'tool:x; rm -rf ~' \"$@\"\n"));
+    }
+
+    fn linux_x86_64() -> Platform {
+        Platform {
+            suffix: "linux-x86_64",
+            os: "linux",
+            goos: "linux",
+            os_title: "Linux",
+            arch: "x86_64",
+            goarch: "amd64",
+            targets: &["x86_64-unknown-linux-musl", "x86_64-unknown-linux-gnu"],
+        }
+    }
+
+    #[test]
+    fn test_default_asset_name() {
+        let candidates = linux_x86_64().asset_candidates(&[], "hfs", "v1.0.0");
+        assert_eq!(
+            candidates,
+            vec![("hfs-linux-x86_64".to_string(), "x86_64-unknown-linux-musl")]
+        );
+    }
+
+    #[test]
+    fn test_asset_patterns_with_fallback() {
+        let patterns = vec![
+            "{name}-{version}-{target}.tar.gz".to_string(),
+            "{name}_{version}_{Os}_{arch}.tar.gz".to_string(),
+        ];
+        let names: Vec<String> = linux_x86_64()
+            .asset_candidates(&patterns, "tool", "v1.2.3")
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "tool-1.2.3-x86_64-unknown-linux-musl.tar.gz",
+                "tool-1.2.3-x86_64-unknown-linux-gnu.tar.gz",
+                "tool_1.2.3_Linux_x86_64.tar.gz",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_installed_names() {
+        let config = ToolConfig {
+            name: "foo".to_string(),
+            native: Some(NativeConfig {
+                binaries: vec!["dist/foo".to_string(), "dist/foo-agent".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(installed_names(&config), vec!["foo", "foo-agent"]);
+    }
+
+    #[test]
+    fn test_release_selector() {
+        let config = |version: Option<&str>, tag: Option<&str>| ToolConfig {
+            name: "foo".to_string(),
+            version: version.map(str::to_string),
+            tag: tag.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            release_selector(&config(None, None)).unwrap(),
+            ReleaseSelector::Latest { prerelease: false }
+        ));
+        assert!(matches!(
+            release_selector(&config(Some("^1.4"), Some("v1.2.0"))).unwrap(),
+            ReleaseSelector::Tag(tag) if tag == "v1.2.0"
+        ));
+        match release_selector(&config(Some("v1.4"), None)).unwrap() {
+            ReleaseSelector::Range { req, .. } => {
+                assert!(req.matches(&semver::Version::new(1, 9, 0)));
+                assert!(!req.matches(&semver::Version::new(2, 0, 0)));
+            }
+            other => panic!("unexpected selector {:?}", other),
+        }
+        assert!(release_selector(&config(Some("not a version"), None)).is_err());
+    }
+
+    #[test]
+    fn test_run_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("foo");
+        fs::write(&bin, "").unwrap();
+        fs::write(dir.path().join("foo-agent"), "").unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+
+        assert_eq!(run_command("{bin}", dir.path(), &bin), vec![path("foo")]);
+        assert_eq!(
+            run_command("foo-agent --daemon", dir.path(), &bin),
+            vec![path("foo-agent"), "--daemon".to_string()]
+        );
+        assert_eq!(
+            run_command("sh {dir}/wrap.sh", dir.path(), &bin),
+            vec!["sh".to_string(), path("wrap.sh")]
+        );
+    }
+}
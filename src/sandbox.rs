@@ -0,0 +1,601 @@
+//! Sandbox for tools that run outside a container (bash scripts, venvs,
+//! native binaries, cargo/go/make builds and node tools)
+//!
+//! A tool declaring `[tools.<tool>.sandbox]` in its registry runs with:
+//!
+//! - read access to the system directories, the tool's own files and `read`
+//! - write access to the directory tbox was started from and `write`
+//! - no network unless `network = true`
+//!
+//! Backends, in order of preference:
+//!
+//! - **bubblewrap** (`bwrap`): the command runs in new namespaces with only
+//!   the allowed paths bind-mounted, a private `/tmp` and, without network,
+//!   an empty network namespace
+//! - **Landlock + seccomp** (Linux 5.13+): filesystem access is restricted
+//!   with Landlock and, without network, a seccomp filter makes creating
+//!   IPv4/IPv6 sockets (and io_uring, which can open them too) fail with `EACCES`
+//!
+//! `TUXBOX_SANDBOX=off` disables the sandbox, `TUXBOX_SANDBOX=always` also
+//! sandboxes tools that declare no policy (with the default one). A tool with a
+//! policy refuses to run when no backend is available.
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{ToolConfig, expand_path};
+use crate::error::TuxBoxError;
+
+/// System locations every sandboxed tool may read (and execute from)
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/etc",
+    "/opt",
+    "/nix/store",
+    "/run/systemd/resolve",
+];
+
+/// Device files sandboxed tools may write to
+const DEVICE_PATHS: &[&str] = &["/dev/null", "/dev/zero", "/dev/tty", "/dev/urandom"];
+
+/// Sandbox policy: `[tools.<tool>.sandbox]` in the registry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// Extra readable paths (`~`, `$HOME` and `$PWD` are expanded)
+    #[serde(default)]
+    pub read: Vec<String>,
+    /// Writable paths (default: the current directory)
+    #[serde(default)]
+    pub write: Vec<String>,
+    /// Allow network access (default: false)
+    #[serde(default)]
+    pub network: bool,
+}
+
+/// Resolved sandbox policy for one run
+#[derive(Debug, PartialEq)]
+struct Policy {
+    read: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+    network: bool,
+}
+
+impl Policy {
+    /// `exec_paths` are the tool's own files (checkout, venv, binary directory)
+    fn resolve(
+        config: &SandboxConfig,
+        exec_paths: &[&Path],
+        home: &Path,
+        cwd: &Path,
+    ) -> Result<Self> {
+        let expand = |spec: &String| -> Result<PathBuf> {
+            let path = expand_path(spec, home, cwd);
+            if path.is_absolute() {
+                Ok(path)
+            } else {
+                Err(TuxBoxError::ConfigError(format!(
+                    "Sandbox path '{}' must be absolute (or start with ~, $HOME, $PWD)",
+                    spec
+                ))
+                .into())
+            }
+        };
+
+        let mut read: Vec<PathBuf> = SYSTEM_READ_PATHS.iter().map(PathBuf::from).collect();
+        read.extend(exec_paths.iter().map(|p| p.to_path_buf()));
+        for spec in &config.read {
+            read.push(expand(spec)?);
+        }
+
+        let write = if config.write.is_empty() {
+            vec![cwd.to_path_buf()]
+        } else {
+            config.write.iter().map(expand).collect::<Result<_>>()?
+        };
+
+        Ok(Policy {
+            read,
+            write,
+            network: config.network,
+        })
+    }
+}
+
+/// How the sandbox is enforced
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Bubblewrap,
+    #[cfg(target_os = "linux")]
+    Landlock,
+}
+
+impl Backend {
+    fn detect() -> Option<Self> {
+        if Command::new("bwrap")
+            .arg("--version")
+            .output()
+            .is_ok_and(|o| o.status.success())
+        {
+            return Some(Backend::Bubblewrap);
+        }
+        #[cfg(target_os = "linux")]
+        if landlock::abi_version() >= 1 {
+            return Some(Backend::Landlock);
+        }
+        None
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Backend::Bubblewrap => "bubblewrap",
+            #[cfg(target_os = "linux")]
+            Backend::Landlock => "landlock",
+        }
+    }
+}
+
+/// Wrap `cmd` in the tool's sandbox, or return it unchanged when the tool
+/// runs unsandboxed. `exec_paths` are the tool's own files, which stay readable.
+pub fn wrap(tool_config: &ToolConfig, cmd: Command, exec_paths: &[&Path]) -> Result<Command> {
+    let mode = std::env::var("TUXBOX_SANDBOX").unwrap_or_default();
+    let config = match (mode.as_str(), &tool_config.sandbox) {
+        ("off", _) => return Ok(cmd),
+        (_, Some(config)) => config.clone(),
+        ("always", None) => SandboxConfig::default(),
+        _ => return Ok(cmd),
+    };
+
+    let backend = Backend::detect().ok_or_else(|| {
+        TuxBoxError::ExecutionError(format!(
+            "'{}' requires a sandbox, but neither bubblewrap (bwrap) nor Landlock is available. \
+             Install bubblewrap, or set TUXBOX_SANDBOX=off to run it unrestricted",
+            tool_config.name
+        ))
+    })?;
+
+    let home = dirs::home_dir().unwrap_or_default();
+    let cwd = std::env::current_dir()?;
+    let policy = Policy::resolve(&config, exec_paths, &home, &cwd)?;
+
+    #[cfg(target_os = "linux")]
+    if backend == Backend::Landlock && !policy.network && !landlock::can_block_network() {
+        return Err(TuxBoxError::ExecutionError(format!(
+            "'{}' must run without network, which Landlock cannot enforce on this architecture. \
+             Install bubblewrap, or set TUXBOX_SANDBOX=off to run it unrestricted",
+            tool_config.name
+        ))
+        .into());
+    }
+
+    println!(
+        "  {} Sandboxed with {} (network: {})",
+        "🔒".cyan(),
+        backend.name(),
+        if policy.network { "allowed" } else { "blocked" }
+    );
+
+    match backend {
+        Backend::Bubblewrap => Ok(bubblewrap_command(&cmd, &policy)),
+        #[cfg(target_os = "linux")]
+        Backend::Landlock => landlock::restrict(cmd, &policy),
+    }
+}
+
+/// Re-create `cmd` as `bwrap <policy> -- <program> <args>`
+fn bubblewrap_command(cmd: &Command, policy: &Policy) -> Command {
+    let mut bwrap = Command::new("bwrap");
+    bwrap.args([
+        "--die-with-parent",
+        "--unshare-user-try",
+        "--unshare-ipc",
+        "--unshare-pid",
+        "--unshare-uts",
+        "--unshare-cgroup-try",
+    ]);
+    if !policy.network {
+        bwrap.arg("--unshare-net");
+    }
+    for path in &policy.read {
+        bwrap.arg("--ro-bind-try").arg(path).arg(path);
+    }
+    bwrap.args(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"]);
+    for path in &policy.write {
+        bwrap.arg("--bind-try").arg(path).arg(path);
+    }
+
+    let dir = cmd
+        .get_current_dir()
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok());
+    if let Some(dir) = dir {
+        bwrap.arg("--chdir").arg(&dir);
+        bwrap.current_dir(dir);
+    }
+
+    bwrap.arg("--").arg(cmd.get_program()).args(cmd.get_args());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => bwrap.env(key, value),
+            None => bwrap.env_remove(key),
+        };
+    }
+    bwrap
+}
+
+/// Landlock rulesets and the seccomp network filter, applied in the child
+/// between fork and exec
+#[cfg(target_os = "linux")]
+mod landlock {
+    use anyhow::{Context, Result};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::process::CommandExt;
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::{DEVICE_PATHS, Policy};
+
+    // Same syscall numbers on every architecture using the generic table
+    const SYS_LANDLOCK_CREATE_RULESET: libc::c_long = 444;
+    const SYS_LANDLOCK_ADD_RULE: libc::c_long = 445;
+    const SYS_LANDLOCK_RESTRICT_SELF: libc::c_long = 446;
+
+    const CREATE_RULESET_VERSION: u32 = 1;
+    const RULE_PATH_BENEATH: u32 = 1;
+
+    const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+    const ACCESS_FS_REFER: u64 = 1 << 13;
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+    /// Every right of Landlock ABI 1 (bits 0..=12)
+    const ACCESS_FS_V1: u64 = (1 << 13) - 1;
+
+    const READ_ACCESS: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+    /// Rights that apply to regular files (the others only to directories)
+    const FILE_ACCESS: u64 =
+        ACCESS_FS_EXECUTE | ACCESS_FS_WRITE_FILE | ACCESS_FS_READ_FILE | ACCESS_FS_TRUNCATE;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    /// Landlock ABI version of the running kernel (0 when unsupported)
+    pub fn abi_version() -> i64 {
+        // SAFETY: querying the version takes no pointers
+        let version = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_CREATE_RULESET,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        version.max(0)
+    }
+
+    /// Restrict `cmd` to `policy` once it is spawned
+    pub fn restrict(mut cmd: Command, policy: &Policy) -> Result<Command> {
+        let abi = abi_version();
+        let mut handled = ACCESS_FS_V1;
+        if abi >= 2 {
+            handled |= ACCESS_FS_REFER;
+        }
+        if abi >= 3 {
+            handled |= ACCESS_FS_TRUNCATE;
+        }
+
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        // SAFETY: `attr` outlives the call and its size is passed along
+        let fd = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_CREATE_RULESET,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0u32,
+            )
+        };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error()).context("Cannot create Landlock ruleset");
+        }
+        // SAFETY: the syscall returned a new file descriptor we now own
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+        for path in &policy.read {
+            add_rule(&ruleset, path, READ_ACCESS & handled)?;
+        }
+        for path in &policy.write {
+            add_rule(&ruleset, path, handled)?;
+        }
+        for path in ["/dev", "/proc"] {
+            add_rule(
+                &ruleset,
+                Path::new(path),
+                ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR,
+            )?;
+        }
+        for path in DEVICE_PATHS.iter().chain(&["/tmp"]) {
+            add_rule(&ruleset, Path::new(path), handled)?;
+        }
+
+        let network = policy.network;
+        // SAFETY: the closure only makes async-signal-safe syscalls and
+        // allocates nothing; the ruleset fd is moved into it
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if libc::syscall(SYS_LANDLOCK_RESTRICT_SELF, ruleset.as_raw_fd(), 0u32) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if !network {
+                    deny_inet_sockets()?;
+                }
+                Ok(())
+            });
+        }
+        Ok(cmd)
+    }
+
+    /// Allow `access` beneath `path`; missing paths are skipped
+    fn add_rule(ruleset: &OwnedFd, path: &Path, access: u64) -> Result<()> {
+        let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return Ok(());
+        };
+        // SAFETY: `c_path` is a valid NUL-terminated string
+        let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Ok(());
+        }
+        // SAFETY: `open` returned a new file descriptor we now own
+        let parent = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let access = if path.is_dir() {
+            access
+        } else {
+            access & FILE_ACCESS
+        };
+        let rule = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: parent.as_raw_fd(),
+        };
+        // SAFETY: `rule` outlives the call
+        let result = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_ADD_RULE,
+                ruleset.as_raw_fd(),
+                RULE_PATH_BENEATH,
+                &rule as *const PathBeneathAttr,
+                0u32,
+            )
+        };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Cannot add Landlock rule for {}", path.display()));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xC000_003E);
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xC000_00B7);
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    const AUDIT_ARCH: Option<u32> = None;
+
+    /// The seccomp network filter only exists for the architectures above
+    pub fn can_block_network() -> bool {
+        AUDIT_ARCH.is_some()
+    }
+
+    /// x32 syscalls on x86_64 have this bit set; they are refused outright
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    const fn stmt(code: u32, k: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    const fn jump(code: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter {
+            code: code as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    /// seccomp filter: `socket(AF_INET | AF_INET6, ...)` and io_uring (whose
+    /// `IORING_OP_SOCKET`/`CONNECT` would bypass the `socket` check) fail with
+    /// EACCES. Syscalls of another ABI (i386 `int 0x80`, x32) kill the process,
+    /// so `socketcall` and friends cannot go around the filter.
+    pub(super) fn network_filter(audit_arch: u32) -> [libc::sock_filter; 14] {
+        use libc::{BPF_ABS, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W};
+
+        const ALLOW: u32 = libc::SECCOMP_RET_ALLOW;
+        const KILL: u32 = libc::SECCOMP_RET_KILL_PROCESS;
+        const DENY: u32 = libc::SECCOMP_RET_ERRNO | libc::EACCES as u32;
+        // Offsets into struct seccomp_data
+        const NR: u32 = 0;
+        const ARCH: u32 = 4;
+        const ARG0: u32 = 16;
+
+        // Jump offsets count from the next instruction: ALLOW is 11, DENY 12, KILL 13
+        [
+            /* 0 */ stmt(BPF_LD | BPF_W | BPF_ABS, ARCH),
+            /* 1 */ jump(BPF_JMP | BPF_JEQ | BPF_K, audit_arch, 0, 11),
+            /* 2 */ stmt(BPF_LD | BPF_W | BPF_ABS, NR),
+            /* 3 */ jump(BPF_JMP | BPF_JGE | BPF_K, X32_SYSCALL_BIT, 9, 0),
+            /* 4 */
+            jump(
+                BPF_JMP | BPF_JEQ | BPF_K,
+                libc::SYS_io_uring_setup as u32,
+                7,
+                0,
+            ),
+            /* 5 */
+            jump(
+                BPF_JMP | BPF_JEQ | BPF_K,
+                libc::SYS_io_uring_enter as u32,
+                6,
+                0,
+            ),
+            /* 6 */
+            jump(
+                BPF_JMP | BPF_JEQ | BPF_K,
+                libc::SYS_io_uring_register as u32,
+                5,
+                0,
+            ),
+            /* 7 */ jump(BPF_JMP | BPF_JEQ | BPF_K, libc::SYS_socket as u32, 0, 3),
+            /* 8 */ stmt(BPF_LD | BPF_W | BPF_ABS, ARG0),
+            /* 9 */ jump(BPF_JMP | BPF_JEQ | BPF_K, libc::AF_INET as u32, 2, 0),
+            /* 10 */ jump(BPF_JMP | BPF_JEQ | BPF_K, libc::AF_INET6 as u32, 1, 0),
+            /* 11 */ stmt(BPF_RET | BPF_K, ALLOW),
+            /* 12 */ stmt(BPF_RET | BPF_K, DENY),
+            /* 13 */ stmt(BPF_RET | BPF_K, KILL),
+        ]
+    }
+
+    fn deny_inet_sockets() -> std::io::Result<()> {
+        let Some(audit_arch) = AUDIT_ARCH else {
+            return Err(std::io::ErrorKind::Unsupported.into());
+        };
+        let filter = network_filter(audit_arch);
+        let program = libc::sock_fprog {
+            len: filter.len() as u16,
+            filter: filter.as_ptr() as *mut libc::sock_filter,
+        };
+        // SAFETY: `program` points to `filter`, which outlives the call
+        let result = unsafe {
+            libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER,
+                &program as *const libc::sock_fprog,
+            )
+        };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a classic BPF program over seccomp_data fields (nr, arch, arg0)
+    #[cfg(target_os = "linux")]
+    fn run_filter(filter: &[libc::sock_filter], nr: u32, arch: u32, arg0: u32) -> u32 {
+        let (mut pc, mut acc) = (0usize, 0u32);
+        loop {
+            let insn = filter[pc];
+            let code = insn.code as u32;
+            match code & 0x07 {
+                libc::BPF_LD => {
+                    acc = match insn.k {
+                        0 => nr,
+                        4 => arch,
+                        16 => arg0,
+                        other => panic!("unexpected offset {other}"),
+                    };
+                    pc += 1;
+                }
+                libc::BPF_JMP => {
+                    let taken = match code & 0xf0 {
+                        libc::BPF_JEQ => acc == insn.k,
+                        libc::BPF_JGE => acc >= insn.k,
+                        op => panic!("unexpected jump {op:#x}"),
+                    };
+                    pc += 1 + if taken { insn.jt } else { insn.jf } as usize;
+                }
+                libc::BPF_RET => return insn.k,
+                class => panic!("unexpected class {class:#x}"),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_network_filter_targets() {
+        const ARCH: u32 = 0xC000_003E;
+        let filter = landlock::network_filter(ARCH);
+        let deny = libc::SECCOMP_RET_ERRNO | libc::EACCES as u32;
+        let run = |nr: i64, arch: u32, arg0: i32| run_filter(&filter, nr as u32, arch, arg0 as u32);
+
+        assert_eq!(run(libc::SYS_socket, ARCH, libc::AF_INET), deny);
+        assert_eq!(run(libc::SYS_socket, ARCH, libc::AF_INET6), deny);
+        assert_eq!(
+            run(libc::SYS_socket, ARCH, libc::AF_UNIX),
+            libc::SECCOMP_RET_ALLOW
+        );
+        assert_eq!(run(libc::SYS_io_uring_setup, ARCH, 0), deny);
+        assert_eq!(run(libc::SYS_io_uring_enter, ARCH, 0), deny);
+        assert_eq!(run(libc::SYS_read, ARCH, 0), libc::SECCOMP_RET_ALLOW);
+        // Foreign ABIs are killed, not merely refused
+        let x32_socket = 0x4000_0000 | libc::SYS_socket;
+        assert_eq!(
+            run(x32_socket, ARCH, libc::AF_INET),
+            libc::SECCOMP_RET_KILL_PROCESS
+        );
+        assert_eq!(
+            run(libc::SYS_socket, 0x4000_0003, libc::AF_INET),
+            libc::SECCOMP_RET_KILL_PROCESS
+        );
+    }
+
+    #[test]
+    fn test_policy_resolve() {
+        let home = Path::new("/home/alice");
+        let cwd = Path::new("/home/alice/work");
+        let tool = Path::new("/home/alice/.tuxbox/tools/scan");
+
+        let policy = Policy::resolve(&SandboxConfig::default(), &[tool], home, cwd).unwrap();
+        assert!(policy.read.contains(&tool.to_path_buf()));
+        assert_eq!(policy.write, vec![cwd.to_path_buf()]);
+        assert!(!policy.network);
+
+        let config = SandboxConfig {
+            read: vec!["~/.config/scan".to_string()],
+            write: vec!["$PWD/out".to_string()],
+            network: true,
+        };
+        let policy = Policy::resolve(&config, &[tool], home, cwd).unwrap();
+        assert!(
+            policy
+                .read
+                .contains(&PathBuf::from("/home/alice/.config/scan"))
+        );
+        assert_eq!(policy.write, vec![PathBuf::from("/home/alice/work/out")]);
+
+        let relative = SandboxConfig {
+            write: vec!["out".to_string()],
+            ..Default::default()
+        };
+        assert!(Policy::resolve(&relative, &[tool], home, cwd).is_err());
+    }
+}
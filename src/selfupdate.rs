@@ -1,37 +1,36 @@
 //! Self-update functionality for TuxBox
 //!
-//! Checks GitHub releases for newer versions and updates the binary.
+//! Checks releases for newer versions and updates the binary. Releases come
+//! from GitHub by default, or from the `[self_update]` source in
+//! `~/.tuxbox/config.toml` (GitHub Enterprise, Gitea, GitLab, HTTP mirror).
+//!
+//! Downloads are verified against the published `.sha256` (and a `.minisig`
+//! when a public key was compiled in via `TUXBOX_MINISIGN_PUBKEY`), and the
+//! new binary must pass a `--version` self-test before it replaces the old one.
+//! The replaced binary is kept as `<exe>.bak` for `tbox self-update --rollback`.
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use serde::Deserialize;
 use std::env;
 use std::fs;
 
+use crate::checksum;
 use crate::error::TuxBoxError;
+use crate::http;
+use crate::release::{Release, ReleaseAsset, ReleaseSource};
 
-const GITHUB_API_URL: &str = "https://api.github.com/repos/disoardi/tuxbox/releases/latest";
+const REPO_URL: &str = "https://github.com/disoardi/tuxbox";
 const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// GitHub API Release response
-#[derive(Debug, Deserialize)]
-struct GithubRelease {
-    tag_name: String,
-    name: String,
-    assets: Vec<GithubAsset>,
-}
-
-#[derive(Debug, Deserialize)]
-struct GithubAsset {
-    name: String,
-    browser_download_url: String,
-}
+/// Release signing key (minisign, base64) baked in at build time.
+/// When set, every self-update must carry a valid `<asset>.minisig`.
+const MINISIGN_PUBLIC_KEY: Option<&str> = option_env!("TUXBOX_MINISIGN_PUBKEY");
 
 /// Check for updates and optionally install them
 pub fn check_for_update(auto_install: bool) -> Result<()> {
     println!("{}", "Checking for updates...".cyan());
 
-    // Fetch latest release from GitHub
+    // Fetch latest release from the configured source
     let release = fetch_latest_release()?;
     let latest_version = parse_version(&release.tag_name)?;
     let current_version = parse_version(&format!("v{}", CURRENT_VERSION))?;
@@ -68,31 +67,21 @@ pub fn check_for_update(auto_install: bool) -> Result<()> {
     }
 
     // Install update
-    install_update(&release)?;
+    install_update(&release, &latest_version)?;
 
     Ok(())
 }
 
-/// Fetch latest release from GitHub API
-fn fetch_latest_release() -> Result<GithubRelease> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("tuxbox")
-        .build()?;
-
-    let response = client
-        .get(GITHUB_API_URL)
-        .send()
-        .context("Failed to fetch latest release from GitHub")?;
-
-    if !response.status().is_success() {
-        anyhow::bail!("GitHub API returned error: {}", response.status());
-    }
+/// Fetch latest release from the configured source (GitHub by default)
+fn fetch_latest_release() -> Result<Release> {
+    let source: ReleaseSource = crate::config::load_config()
+        .ok()
+        .and_then(|c| c.self_update)
+        .unwrap_or_default();
 
-    let release: GithubRelease = response
-        .json()
-        .context("Failed to parse GitHub API response")?;
-
-    Ok(release)
+    source
+        .latest_release(REPO_URL)
+        .context("Failed to fetch latest release")
 }
 
 /// Parse version string (removes 'v' prefix if present)
@@ -101,7 +90,7 @@ fn parse_version(version: &str) -> Result<String> {
 }
 
 /// Install update by downloading and replacing the binary
-fn install_update(release: &GithubRelease) -> Result<()> {
+fn install_update(release: &Release, expected_version: &str) -> Result<()> {
     println!("{}", "\nInstalling update...".cyan());
 
     // Detect current platform
@@ -112,29 +101,28 @@ fn install_update(release: &GithubRelease) -> Result<()> {
         asset_name.dimmed()
     );
 
-    // Find matching asset (.tar.gz only, skip .sha256)
+    // Find matching asset (.tar.gz; its .sha256/.minisig are fetched for verification)
     let tarball_name = format!("{}.tar.gz", asset_name);
-    let asset = release
-        .assets
-        .iter()
-        .find(|a| a.name == tarball_name)
-        .ok_or_else(|| {
-            let available: Vec<&str> = release.assets.iter().map(|a| a.name.as_str()).collect();
-            TuxBoxError::UpdateError(format!(
-                "No binary found for platform: {}\nExpected asset: {}\nAvailable assets: {}",
-                asset_name,
-                tarball_name,
-                available.join(", ")
-            ))
-        })?;
+    let asset = release.asset(&tarball_name).ok_or_else(|| {
+        let available = release.asset_names();
+        TuxBoxError::UpdateError(format!(
+            "No binary found for platform: {}\nExpected asset: {}\nAvailable assets: {}",
+            asset_name,
+            tarball_name,
+            available.join(", ")
+        ))
+    })?;
 
     println!("  {} Downloading: {}", "→".cyan(), asset.name.bold());
 
     // Download binary
-    let binary_data = download_asset(&asset.browser_download_url)?;
+    let binary_data = download_asset(&asset)?;
+
+    // Refuse to go further unless the download is the published artifact
+    verify_release_asset(release, &asset.name, &binary_data)?;
 
     // Extract from tarball and replace current binary
-    replace_current_binary(&binary_data)?;
+    replace_current_binary(&binary_data, expected_version)?;
 
     println!("{}", "✓ Update installed successfully!".green());
     println!(
@@ -145,82 +133,154 @@ fn install_update(release: &GithubRelease) -> Result<()> {
     Ok(())
 }
 
-/// Download asset from GitHub
-fn download_asset(url: &str) -> Result<Vec<u8>> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("tuxbox")
-        .build()?;
-
-    let response = client
-        .get(url)
-        .send()
-        .context("Failed to download update")?;
+/// Verify a downloaded asset against its published checksum and, if a key is
+/// compiled in, its minisign signature
+fn verify_release_asset(release: &Release, asset_name: &str, data: &[u8]) -> Result<()> {
+    let find = |name: String| release.asset(&name);
+
+    let checksum_asset = find(format!("{}.sha256", asset_name)).ok_or_else(|| {
+        TuxBoxError::UpdateError(format!(
+            "No checksum published for {}, refusing to install an unverified update",
+            asset_name
+        ))
+    })?;
+    let checksum_file = download_asset(&checksum_asset)?;
+    let expected = checksum::expected_digest(&String::from_utf8_lossy(&checksum_file), asset_name)
+        .ok_or_else(|| {
+            TuxBoxError::UpdateError(format!("Malformed checksum file: {}", checksum_asset.name))
+        })?;
+    checksum::verify(data, &expected, asset_name)?;
+    println!("  {} Checksum verified", "✓".green());
 
-    if !response.status().is_success() {
-        anyhow::bail!("Download failed: {}", response.status());
+    if let Some(public_key) = MINISIGN_PUBLIC_KEY {
+        let signature_asset = find(format!("{}.minisig", asset_name)).ok_or_else(|| {
+            TuxBoxError::SignatureError(format!("No signature published for {}", asset_name))
+        })?;
+        let signature = download_asset(&signature_asset)?;
+        checksum::verify_minisign(
+            data,
+            &String::from_utf8_lossy(&signature),
+            public_key,
+            asset_name,
+        )?;
+        println!("  {} Signature verified", "✓".green());
     }
 
-    let bytes = response.bytes()?.to_vec();
+    Ok(())
+}
+
+/// Download a release asset
+fn download_asset(asset: &ReleaseAsset) -> Result<Vec<u8>> {
+    let bytes = http::download_asset(asset).context("Failed to download update")?;
     println!("  {} Downloaded {} bytes", "✓".green(), bytes.len());
 
     Ok(bytes)
 }
 
 /// Replace current binary with the new one
-fn replace_current_binary(tarball_data: &[u8]) -> Result<()> {
-    use flate2::read::GzDecoder;
-    use tar::Archive;
-
+fn replace_current_binary(tarball_data: &[u8], expected_version: &str) -> Result<()> {
     println!("  {} Extracting binary...", "→".cyan());
 
     // Get current binary path
     let current_exe = env::current_exe().context("Failed to get current executable path")?;
 
-    // Extract tarball
-    let decoder = GzDecoder::new(tarball_data);
-    let mut archive = Archive::new(decoder);
-
     // Find tbox binary in archive
-    for entry in archive.entries()? {
-        let mut entry = entry?;
-        let path = entry.path()?;
-
-        if path.file_name() == Some(std::ffi::OsStr::new("tbox")) {
-            println!("  {} Replacing binary...", "→".cyan());
-
-            // Create backup
-            let backup_path = current_exe.with_extension("bak");
-            fs::copy(&current_exe, &backup_path).context("Failed to create backup")?;
-
-            // Extract to temp location first
-            let temp_path = current_exe.with_extension("tmp");
-            let mut temp_file = fs::File::create(&temp_path)?;
-            std::io::copy(&mut entry, &mut temp_file)?;
-
-            // Make executable (Unix)
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                let mut perms = fs::metadata(&temp_path)?.permissions();
-                perms.set_mode(0o755);
-                fs::set_permissions(&temp_path, perms)?;
-            }
-
-            // Replace current binary
-            fs::rename(&temp_path, &current_exe).context("Failed to replace binary")?;
-
-            println!("  {} Binary replaced successfully", "✓".green());
-            println!(
-                "  {} Backup saved to: {}",
-                "→".dimmed(),
-                backup_path.display().to_string().dimmed()
-            );
+    let binary = crate::archive::extract_files("tbox.tar.gz", tarball_data, &["tbox".into()])
+        .map_err(|_| TuxBoxError::UpdateError("Binary not found in archive".into()))?
+        .remove(0);
+
+    // Extract to temp location first
+    let temp_path = current_exe.with_extension("tmp");
+    fs::write(&temp_path, &binary)?;
+
+    // Make executable (Unix)
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&temp_path)?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&temp_path, perms)?;
+    }
 
-            return Ok(());
-        }
+    // Make sure the new binary actually runs on this machine before swapping
+    if let Err(e) = self_test(&temp_path, expected_version) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    println!("  {} Replacing binary...", "→".cyan());
+
+    // Create backup
+    let backup_path = current_exe.with_extension("bak");
+    fs::copy(&current_exe, &backup_path).context("Failed to create backup")?;
+
+    // Replace current binary
+    fs::rename(&temp_path, &current_exe).context("Failed to replace binary")?;
+
+    println!("  {} Binary replaced successfully", "✓".green());
+    println!(
+        "  {} Backup saved to: {} (restore with 'tbox self-update --rollback')",
+        "→".dimmed(),
+        backup_path.display().to_string().dimmed()
+    );
+
+    Ok(())
+}
+
+/// Run `<binary> --version` and check it reports the expected version
+fn self_test(binary: &std::path::Path, expected_version: &str) -> Result<()> {
+    println!("  {} Running self-test...", "→".cyan());
+
+    let output = std::process::Command::new(binary)
+        .arg("--version")
+        .output()
+        .map_err(|e| TuxBoxError::UpdateError(format!("New binary failed to start: {}", e)))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = expected_version
+        .strip_prefix('v')
+        .unwrap_or(expected_version);
+    if !output.status.success() || reported_version(&stdout) != Some(expected) {
+        return Err(TuxBoxError::UpdateError(format!(
+            "Self-test failed: expected version {}, got '{}'",
+            expected_version,
+            stdout.trim()
+        ))
+        .into());
     }
 
-    Err(TuxBoxError::UpdateError("Binary not found in archive".into()).into())
+    println!("  {} Self-test passed ({})", "✓".green(), stdout.trim());
+    Ok(())
+}
+
+/// Version from `tbox --version` output ("tbox 0.2.22" → "0.2.22")
+fn reported_version(output: &str) -> Option<&str> {
+    let version = output.split_whitespace().nth(1)?;
+    Some(version.strip_prefix('v').unwrap_or(version))
+}
+
+/// Restore the binary saved as `<exe>.bak` by the last self-update
+pub fn rollback_update() -> Result<()> {
+    let current_exe = env::current_exe().context("Failed to get current executable path")?;
+    let backup_path = current_exe.with_extension("bak");
+
+    if !backup_path.exists() {
+        return Err(TuxBoxError::UpdateError(format!(
+            "No backup found at {}",
+            backup_path.display()
+        ))
+        .into());
+    }
+
+    println!(
+        "{} Restoring {}...",
+        "→".cyan(),
+        backup_path.display().to_string().dimmed()
+    );
+    fs::rename(&backup_path, &current_exe).context("Failed to restore backup")?;
+
+    println!("{}", "✓ Previous version restored!".green());
+    Ok(())
 }
 
 /// Get platform-specific asset name
@@ -258,6 +318,15 @@ mod tests {
         assert_eq!(parse_version("1.2.3").unwrap(), "1.2.3");
     }
 
+    #[test]
+    fn test_reported_version() {
+        assert_eq!(reported_version("tbox 0.2.22\n"), Some("0.2.22"));
+        assert_eq!(reported_version("tbox v0.2.22"), Some("0.2.22"));
+        // 0.2.2 must not pass for 0.2.22
+        assert_ne!(reported_version("tbox 0.2.22"), Some("0.2.2"));
+        assert_eq!(reported_version("garbage"), None);
+    }
+
     #[test]
     fn test_platform_detection() {
         let platform = get_platform_asset_name().unwrap();
@@ -0,0 +1,326 @@
+//! Signature checks for Git revisions and signed files
+//!
+//! - SSH signatures (`git commit -S` / `git tag -s` with `gpg.format=ssh`) are
+//!   checked against an `allowed_signers` file with `ssh-keygen -Y`, as
+//!   `git verify-commit` does
+//! - GPG signatures are checked with `gpg` in a throwaway keyring holding only
+//!   the allowed public keys
+//! - Detached minisign signatures (`<file>.minisig`) are checked against base64 public keys
+//!
+//! The last revision of a checkout that passed verification is remembered in
+//! `.git/tuxbox-verified`, so unchanged checkouts are not re-verified.
+
+use anyhow::{Context, Result};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::checksum;
+use crate::error::TuxBoxError;
+
+/// Namespace git uses for SSH commit and tag signatures
+const SSH_NAMESPACE: &str = "git";
+
+const SSH_SIGNATURE: &str = "-----BEGIN SSH SIGNATURE-----";
+const PGP_SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----";
+
+/// Marker (inside `.git`) recording the last revision that passed verification
+const VERIFIED_MARKER: &str = ".git/tuxbox-verified";
+
+/// Signature requirements for tool repositories: `[signing]` (every tool of
+/// the registry) or `[tools.<tool>.signing]` in tools.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolSigning {
+    /// Refuse tool revisions without a valid signature from an allowed key
+    #[serde(default)]
+    pub require_signed: bool,
+    /// SSH `allowed_signers` file, relative to the registry root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_signers: Option<String>,
+    /// ASCII-armored GPG public keys, relative to the registry root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gpg_keys: Vec<String>,
+}
+
+impl ToolSigning {
+    /// Resolve the key paths against the registry checkout they come from
+    pub fn resolve_paths(&mut self, registry_dir: &Path) {
+        let resolve = |path: &mut String| {
+            *path = registry_dir.join(&*path).to_string_lossy().into_owned();
+        };
+        if let Some(signers) = self.allowed_signers.as_mut() {
+            resolve(signers);
+        }
+        self.gpg_keys.iter_mut().for_each(resolve);
+    }
+}
+
+/// Check that commit `oid`, or a signed annotated tag pointing at it, carries a
+/// valid signature from a key allowed by `policy`. Returns who signed it.
+pub fn verify_revision(repo: &Repository, oid: Oid, policy: &ToolSigning) -> Result<String> {
+    let short = &oid.to_string()[..12];
+    if policy.allowed_signers.is_none() && policy.gpg_keys.is_empty() {
+        return Err(TuxBoxError::ConfigError(
+            "require_signed is set, but neither allowed_signers nor gpg_keys is configured".into(),
+        )
+        .into());
+    }
+
+    let commit_error = match repo.extract_signature(&oid, None) {
+        Ok((signature, signed_data)) => {
+            match verify_signature(signature.as_str().unwrap_or_default(), &signed_data, policy) {
+                Ok(signer) => return Ok(signer),
+                Err(e) => e,
+            }
+        }
+        Err(_) => TuxBoxError::SignatureError(format!("commit {} is not signed", short)).into(),
+    };
+
+    // Pinned releases are often signed tags on unsigned commits
+    for tag_oid in annotated_tags_of(repo, oid) {
+        let odb = repo.odb()?;
+        let object = odb.read(tag_oid)?;
+        let raw = String::from_utf8_lossy(object.data());
+        let start = raw.find(SSH_SIGNATURE).or_else(|| raw.find(PGP_SIGNATURE));
+        if let Some(start) = start
+            && let Ok(signer) = verify_signature(&raw[start..], raw[..start].as_bytes(), policy)
+        {
+            return Ok(signer);
+        }
+    }
+
+    Err(commit_error)
+}
+
+/// Check the SSH signature of commit `oid` against `allowed_signers`.
+/// Returns the principal (usually an email) of the key that signed it.
+pub fn verify_commit(repo: &Repository, oid: Oid, allowed_signers: &Path) -> Result<String> {
+    let short = &oid.to_string()[..12];
+    let (signature, signed_data) = repo
+        .extract_signature(&oid, None)
+        .map_err(|_| TuxBoxError::SignatureError(format!("commit {} is not signed", short)))?;
+    let signature = signature.as_str().unwrap_or_default();
+    if !signature.contains(SSH_SIGNATURE) {
+        return Err(
+            TuxBoxError::SignatureError(format!("commit {} has no SSH signature", short)).into(),
+        );
+    }
+    verify_ssh(signature, &signed_data, allowed_signers)
+}
+
+/// Check a detached minisign signature over `data` against any of `keys`
+pub fn verify_minisign_any(
+    data: &[u8],
+    signature: &str,
+    keys: &[String],
+    name: &str,
+) -> Result<()> {
+    let mut last_error = None;
+    for key in keys {
+        match checksum::verify_minisign(data, signature, key, name) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        TuxBoxError::SignatureError(format!("no minisign key configured for {}", name)).into()
+    }))
+}
+
+/// Contents of `path` in the tree of commit `oid`, without checking it out
+pub fn file_at(repo: &Repository, oid: Oid, path: &str) -> Result<Vec<u8>> {
+    let tree = repo.find_commit(oid)?.tree()?;
+    let entry = tree
+        .get_path(Path::new(path))
+        .with_context(|| format!("{} not found in commit {}", path, oid))?;
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    Ok(blob.content().to_vec())
+}
+
+/// Last revision of the checkout at `dir` that passed verification
+pub fn verified_revision(dir: &Path) -> Option<String> {
+    std::fs::read_to_string(dir.join(VERIFIED_MARKER))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// Remember `oid` as verified for the checkout at `dir`
+pub fn record_verified(dir: &Path, oid: Oid) -> Result<()> {
+    std::fs::write(dir.join(VERIFIED_MARKER), oid.to_string())?;
+    Ok(())
+}
+
+/// True when the checked-out HEAD of `dir` is the last verified revision
+pub fn is_head_verified(dir: &Path) -> bool {
+    let head = Repository::open(dir)
+        .and_then(|repo| repo.head()?.peel_to_commit().map(|c| c.id().to_string()));
+    head.is_ok_and(|head| verified_revision(dir).as_deref() == Some(head.as_str()))
+}
+
+/// Annotated tags whose target is `oid`
+fn annotated_tags_of(repo: &Repository, oid: Oid) -> Vec<Oid> {
+    let mut tags = Vec::new();
+    let _ = repo.tag_foreach(|tag_oid, _| {
+        if let Ok(tag) = repo.find_tag(tag_oid)
+            && tag.target_id() == oid
+        {
+            tags.push(tag_oid);
+        }
+        true
+    });
+    tags
+}
+
+/// Dispatch on the signature type (SSH or GPG)
+fn verify_signature(signature: &str, signed_data: &[u8], policy: &ToolSigning) -> Result<String> {
+    if signature.contains(SSH_SIGNATURE) {
+        let signers = policy.allowed_signers.as_ref().ok_or_else(|| {
+            TuxBoxError::SignatureError("SSH signature, but no allowed_signers configured".into())
+        })?;
+        verify_ssh(signature, signed_data, Path::new(signers))
+    } else if signature.contains(PGP_SIGNATURE) {
+        if policy.gpg_keys.is_empty() {
+            return Err(TuxBoxError::SignatureError(
+                "GPG signature, but no gpg_keys configured".into(),
+            )
+            .into());
+        }
+        verify_gpg(signature, signed_data, &policy.gpg_keys)
+    } else {
+        Err(TuxBoxError::SignatureError("unsupported signature format".into()).into())
+    }
+}
+
+fn verify_ssh(signature: &str, signed_data: &[u8], allowed_signers: &Path) -> Result<String> {
+    if !allowed_signers.is_file() {
+        return Err(TuxBoxError::ConfigError(format!(
+            "allowed_signers file not found: {}",
+            allowed_signers.display()
+        ))
+        .into());
+    }
+
+    let mut signature_file = tempfile::NamedTempFile::with_prefix("tuxbox-")?;
+    signature_file.write_all(signature.as_bytes())?;
+    signature_file.flush()?;
+    let output = Command::new("ssh-keygen")
+        .args(["-Y", "find-principals", "-s"])
+        .arg(signature_file.path())
+        .arg("-f")
+        .arg(allowed_signers)
+        .output()
+        .context("Failed to run ssh-keygen (OpenSSH 8.2+ is needed for SSH signatures)")?;
+    let principals = String::from_utf8_lossy(&output.stdout);
+
+    for principal in principals.lines().map(str::trim).filter(|p| !p.is_empty()) {
+        let mut verify = Command::new("ssh-keygen");
+        verify
+            .args(["-Y", "verify", "-n", SSH_NAMESPACE, "-I", principal, "-s"])
+            .arg(signature_file.path())
+            .arg("-f")
+            .arg(allowed_signers);
+        if run_with_stdin(verify, signed_data)?.status.success() {
+            return Ok(principal.to_string());
+        }
+    }
+
+    Err(TuxBoxError::SignatureError(format!(
+        "not signed by a key in {}",
+        allowed_signers.display()
+    ))
+    .into())
+}
+
+fn verify_gpg(signature: &str, signed_data: &[u8], keys: &[String]) -> Result<String> {
+    // Private (0700) throwaway GNUPGHOME
+    let home = tempfile::Builder::new().prefix("tuxbox-gnupg-").tempdir()?;
+
+    let import = Command::new("gpg")
+        .args(["--batch", "--quiet", "--homedir"])
+        .arg(home.path())
+        .arg("--import")
+        .args(keys)
+        .output()
+        .context("Failed to run gpg (GnuPG is needed for GPG signatures)")?;
+    if !import.status.success() {
+        return Err(TuxBoxError::ConfigError(format!(
+            "Cannot import gpg_keys: {}",
+            String::from_utf8_lossy(&import.stderr).trim()
+        ))
+        .into());
+    }
+
+    let signature_file = home.path().join("signature.asc");
+    std::fs::write(&signature_file, signature)?;
+    let mut verify = Command::new("gpg");
+    verify
+        .args(["--batch", "--status-fd", "1", "--homedir"])
+        .arg(home.path())
+        .arg("--verify")
+        .arg(&signature_file)
+        .arg("-");
+    let output = run_with_stdin(verify, signed_data)?;
+
+    // The keyring holds only the allowed keys, so any VALIDSIG is from one of them
+    let status = String::from_utf8_lossy(&output.stdout);
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("[GNUPG:] VALIDSIG "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(|fingerprint| format!("GPG key {}", fingerprint))
+        .ok_or_else(|| {
+            TuxBoxError::SignatureError("not signed by one of the gpg_keys".into()).into()
+        })
+}
+
+fn run_with_stdin(mut cmd: Command, input: &[u8]) -> Result<std::process::Output> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", cmd.get_program().to_string_lossy()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    Ok(child.wait_with_output()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsigned_commit_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let tree_id = {
+            let mut builder = repo.treebuilder(None).unwrap();
+            let blob = repo.blob(b"[tools]\n").unwrap();
+            builder.insert("tools.toml", blob, 0o100644).unwrap();
+            builder.write().unwrap()
+        };
+        let tree = repo.find_tree(tree_id).unwrap();
+        let author = git2::Signature::now("a", "a@example.com").unwrap();
+        let oid = repo
+            .commit(Some("HEAD"), &author, &author, "init", &tree, &[])
+            .unwrap();
+
+        let signers = dir.path().join("allowed_signers");
+        std::fs::write(&signers, "").unwrap();
+        let err = verify_commit(&repo, oid, &signers).unwrap_err();
+        assert!(err.to_string().contains("not signed"));
+
+        let policy = ToolSigning {
+            require_signed: true,
+            allowed_signers: Some(signers.to_string_lossy().into_owned()),
+            gpg_keys: Vec::new(),
+        };
+        assert!(verify_revision(&repo, oid, &policy).is_err());
+
+        assert_eq!(file_at(&repo, oid, "tools.toml").unwrap(), b"[tools]\n");
+        assert!(file_at(&repo, oid, "tools.toml.minisig").is_err());
+    }
+}
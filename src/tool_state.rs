@@ -5,10 +5,14 @@
 //! `~/.tuxbox/tools/<tool>/.tuxbox-state.toml`
 //!
 //! # Lifecycle
-//! - **Written** after a successful first installation (venv created + deps installed)
-//! - **Read** at the start of every `tbox run` — if valid, setup is skipped
-//! - **Invalidated** (file deleted) when `tbox update` pulls new commits, so the
-//!   next run re-installs dependencies against the updated source
+//! - **Written** after a successful first installation (venv created + deps installed,
+//!   binary built for cargo/go/make tools, or node_modules installed)
+//! - **Read** at the start of every `tbox run` — if valid, setup is skipped.
+//!   Venv installs also record an [`InstallFingerprint`] (dependency file hashes,
+//!   HEAD commit, interpreter path and version) and are redone when it changes
+//! - **Invalidated** when `tbox update` pulls new commits, so the next run
+//!   re-installs dependencies against the updated source. The revision history
+//!   (used by `tbox rollback`) survives invalidation.
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -21,10 +25,21 @@ const STATE_FILE: &str = ".tuxbox-state.toml";
 pub struct ToolState {
     /// Schema version — bump if the format changes incompatibly.
     pub version: String,
-    /// How the tool was installed: "venv", "docker", "bash"
+    /// How the tool was installed: "venv", "docker", "bash", "cargo", "go", "make", "node".
+    /// Empty when the install was invalidated but the history was kept.
+    #[serde(default)]
     pub method: String,
     /// Venv details (populated when method = "venv").
     pub venv: Option<VenvState>,
+    /// Build details (populated for cargo/go/make tools).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildState>,
+    /// node_modules details (populated when method = "node").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<NodeState>,
+    /// Revision recorded by the last successful update (for `tbox rollback`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<RevisionHistory>,
 }
 
 /// Virtual-environment details saved after a successful `run_in_venv` install.
@@ -34,18 +49,99 @@ pub struct VenvState {
     pub path: PathBuf,
     /// Python executable used to create the venv (may be a pyenv path).
     pub python: String,
+    /// Inputs of the install; any change triggers a reinstall.
+    /// Missing in state files written by older versions (treated as stale).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<InstallFingerprint>,
+}
+
+/// What a venv install depended on, compared on every run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstallFingerprint {
+    /// SHA-256 over the dependency files (requirements.txt, pyproject.toml, ...).
+    pub deps_hash: String,
+    /// HEAD commit of the tool's repository.
+    pub commit: Option<String>,
+    /// Resolved path of the venv's interpreter.
+    pub interpreter: Option<String>,
+    /// `python --version` of that interpreter.
+    pub interpreter_version: Option<String>,
+    /// Hash of the wheelhouse listing, when one was available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wheelhouse: Option<String>,
+}
+
+/// Binary produced by a build-from-source tool, cached by commit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildState {
+    /// Commit the binary was built from; a different HEAD forces a rebuild.
+    pub commit: String,
+    /// Absolute path to the built executable.
+    pub binary: PathBuf,
+}
+
+/// node_modules install saved after a successful `run_node_tool` install.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeState {
+    /// Package manager that performed the install: "npm", "pnpm" or "yarn".
+    pub manager: String,
+    /// SHA-256 of the lockfile (or package.json without one) at install time.
+    pub lockfile_hash: String,
+}
+
+/// Revision bookkeeping used by `tbox rollback`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RevisionHistory {
+    /// Commit hash (git tools) or release tag (native tools) that was
+    /// installed before the last successful update.
+    pub previous: Option<String>,
+    /// Set by `tbox rollback`: `tbox update` (all tools) skips pinned tools
+    /// until the user updates this one explicitly.
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 impl ToolState {
     /// Construct a state record for a venv-based installation.
-    pub fn for_venv(venv_path: PathBuf, python: String) -> Self {
+    pub fn for_venv(venv_path: PathBuf, python: String, fingerprint: InstallFingerprint) -> Self {
         ToolState {
             version: "1".to_string(),
             method: "venv".to_string(),
             venv: Some(VenvState {
                 path: venv_path,
                 python,
+                fingerprint: Some(fingerprint),
+            }),
+            build: None,
+            node: None,
+            history: None,
+        }
+    }
+
+    /// Construct a state record for a node_modules install.
+    pub fn for_node(manager: &str, lockfile_hash: String) -> Self {
+        ToolState {
+            version: "1".to_string(),
+            method: "node".to_string(),
+            venv: None,
+            build: None,
+            node: Some(NodeState {
+                manager: manager.to_string(),
+                lockfile_hash,
             }),
+            history: None,
+        }
+    }
+
+    /// Construct a state record for a binary built with `method` (cargo/go/make).
+    pub fn for_build(method: &str, commit: String, binary: PathBuf) -> Self {
+        ToolState {
+            version: "1".to_string(),
+            method: method.to_string(),
+            venv: None,
+            build: Some(BuildState { commit, binary }),
+            node: None,
+            history: None,
         }
     }
 
@@ -54,34 +150,196 @@ impl ToolState {
     /// Returns `None` when:
     /// - The state file does not exist (first run)
     /// - The file cannot be parsed (schema mismatch / corruption)
-    /// - The recorded venv directory no longer exists (was deleted)
+    /// - The install was invalidated (only the revision history is left)
+    /// - The recorded venv directory or built binary no longer exists (was deleted)
     pub fn load(tool_path: &Path) -> Option<Self> {
-        let state_file = tool_path.join(STATE_FILE);
-        if !state_file.exists() {
+        let state = Self::read(tool_path)?;
+
+        if state.method.is_empty() {
             return None;
         }
 
-        let content = std::fs::read_to_string(&state_file).ok()?;
-        let state: ToolState = toml::from_str(&content).ok()?;
-
         // Validate: venv directory must still exist
         if state.venv.as_ref().is_some_and(|venv| !venv.path.exists()) {
             return None;
         }
+        if state.build.as_ref().is_some_and(|b| !b.binary.exists()) {
+            return None;
+        }
 
         Some(state)
     }
 
     /// Persist the state to the tool directory.
+    ///
+    /// The revision history already on disk is carried over when `self`
+    /// does not set one, so a reinstall never forgets the rollback target.
     pub fn save(&self, tool_path: &Path) -> Result<()> {
-        let content = toml::to_string_pretty(self)?;
+        let content = if self.history.is_none() {
+            let history = Self::read(tool_path).and_then(|s| s.history);
+            toml::to_string_pretty(&ToolState {
+                version: self.version.clone(),
+                method: self.method.clone(),
+                venv: self.venv.clone(),
+                build: self.build.clone(),
+                node: self.node.clone(),
+                history,
+            })?
+        } else {
+            toml::to_string_pretty(self)?
+        };
         std::fs::write(tool_path.join(STATE_FILE), content)?;
         Ok(())
     }
 
-    /// Remove the state file, forcing a full reinstall on the next run.
+    /// Drop the install record, forcing a full reinstall on the next run.
     /// Called by `tbox update` after pulling new commits.
+    ///
+    /// The state file is removed entirely unless it carries revision history.
     pub fn invalidate(tool_path: &Path) {
-        let _ = std::fs::remove_file(tool_path.join(STATE_FILE));
+        match Self::read(tool_path).and_then(|s| s.history) {
+            Some(history) => {
+                let _ = Self::history_only(history).save(tool_path);
+            }
+            None => {
+                let _ = std::fs::remove_file(tool_path.join(STATE_FILE));
+            }
+        }
+    }
+
+    /// Remember `previous` as the rollback target after a successful update.
+    pub fn record_previous(tool_path: &Path, previous: &str) -> Result<()> {
+        Self::update_history(tool_path, |history| {
+            history.previous = Some(previous.to_string());
+            history.pinned = false;
+        })
+    }
+
+    /// Revision history recorded for the tool, if any.
+    pub fn history(tool_path: &Path) -> Option<RevisionHistory> {
+        Self::read(tool_path).and_then(|s| s.history)
+    }
+
+    /// True when the tool was rolled back and must not be updated implicitly.
+    pub fn is_pinned(tool_path: &Path) -> bool {
+        Self::history(tool_path).is_some_and(|h| h.pinned)
+    }
+
+    /// Pin or unpin the tool, keeping the rest of the state untouched.
+    pub fn set_pinned(tool_path: &Path, pinned: bool) -> Result<()> {
+        Self::update_history(tool_path, |history| history.pinned = pinned)
+    }
+
+    /// Record a completed rollback: the rollback target is consumed and the
+    /// tool stays pinned until the next explicit update.
+    pub fn record_rollback(tool_path: &Path) -> Result<()> {
+        Self::update_history(tool_path, |history| {
+            history.previous = None;
+            history.pinned = true;
+        })
+    }
+
+    /// Rewrite absolute paths recorded under `from` (a TuxBox home on another
+    /// host) to live under `to`. Used by `tbox bundle import`.
+    pub fn rebase(tool_path: &Path, from: &Path, to: &Path) -> Result<()> {
+        let Some(mut state) = Self::read(tool_path) else {
+            return Ok(());
+        };
+        let rebase_path = |path: &mut PathBuf| {
+            if let Ok(relative) = path.strip_prefix(from) {
+                *path = to.join(relative);
+            }
+        };
+        if let Some(venv) = state.venv.as_mut() {
+            rebase_path(&mut venv.path);
+        }
+        if let Some(build) = state.build.as_mut() {
+            rebase_path(&mut build.binary);
+        }
+        std::fs::write(tool_path.join(STATE_FILE), toml::to_string_pretty(&state)?)?;
+        Ok(())
+    }
+
+    /// Read the state file without validating the install.
+    fn read(tool_path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(tool_path.join(STATE_FILE)).ok()?;
+        toml::from_str(&content).ok()
+    }
+
+    fn history_only(history: RevisionHistory) -> Self {
+        ToolState {
+            version: "1".to_string(),
+            method: String::new(),
+            venv: None,
+            build: None,
+            node: None,
+            history: Some(history),
+        }
+    }
+
+    fn update_history(tool_path: &Path, f: impl FnOnce(&mut RevisionHistory)) -> Result<()> {
+        let mut state =
+            Self::read(tool_path).unwrap_or_else(|| Self::history_only(Default::default()));
+        f(state.history.get_or_insert_with(Default::default));
+        state.save(tool_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_survives_invalidate() {
+        let dir = tempfile::tempdir().unwrap();
+        let venv = dir.path().join("venv");
+        std::fs::create_dir(&venv).unwrap();
+
+        let fingerprint = InstallFingerprint {
+            deps_hash: "0".repeat(64),
+            commit: None,
+            interpreter: None,
+            interpreter_version: None,
+            wheelhouse: None,
+        };
+        ToolState::for_venv(venv, "python3".into(), fingerprint)
+            .save(dir.path())
+            .unwrap();
+        ToolState::record_previous(dir.path(), "abc123").unwrap();
+        assert!(ToolState::load(dir.path()).is_some());
+
+        ToolState::invalidate(dir.path());
+        assert!(ToolState::load(dir.path()).is_none());
+        assert_eq!(
+            ToolState::history(dir.path()).unwrap().previous.as_deref(),
+            Some("abc123")
+        );
+
+        ToolState::record_rollback(dir.path()).unwrap();
+        assert!(ToolState::is_pinned(dir.path()));
+    }
+
+    #[test]
+    fn test_rebase_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        ToolState::for_build(
+            "cargo",
+            "abc123".into(),
+            PathBuf::from("/home/alice/.tuxbox/build/foo/release/foo"),
+        )
+        .save(dir.path())
+        .unwrap();
+
+        ToolState::rebase(
+            dir.path(),
+            Path::new("/home/alice/.tuxbox"),
+            Path::new("/opt/tuxbox"),
+        )
+        .unwrap();
+        let state = ToolState::read(dir.path()).unwrap();
+        assert_eq!(
+            state.build.unwrap().binary,
+            PathBuf::from("/opt/tuxbox/build/foo/release/foo")
+        );
     }
 }
@@ -0,0 +1,499 @@
+//! Trust store and admin policy for registry tools
+//!
+//! # Trust store (`~/.tuxbox/trust.toml`)
+//! The first run of a registry tool shows its repository and commands and asks
+//! for confirmation. The approved entry is recorded, and the prompt comes
+//! back whenever the registry changes any of it (repo, commands, container
+//! flags, sandbox, release source, package index, ...).
+//! Tools of a trusted registry (`tbox trust registry <name>`) run without
+//! prompting.
+//!
+//! # Admin policy (`/etc/tuxbox/policy.toml`)
+//! Restricts which repositories registry tools may come from:
+//!
+//! ```toml
+//! allowed_hosts = ["github.com", "*.corp.example"]
+//! allowed_prefixes = ["https://gitlab.com/acme/"]
+//! ```
+//!
+//! A tool with a repo, release source or package index URL that matches neither
+//! list is refused, whatever the trust store says.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, RegistryConfig, ToolConfig};
+use crate::credentials::host_of;
+use crate::error::TuxBoxError;
+
+/// System-wide policy file, managed by the administrator
+const POLICY_FILE: &str = "/etc/tuxbox/policy.toml";
+
+/// Allowed sources of registry tools
+#[derive(Debug, Default, Deserialize)]
+pub struct Policy {
+    /// Repo hosts (`github.com`) or host wildcards (`*.corp.example`)
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Repo URL prefixes (`https://github.com/acme/`)
+    #[serde(default)]
+    pub allowed_prefixes: Vec<String>,
+}
+
+impl Policy {
+    /// Load the admin policy; no file means no restrictions
+    pub fn load() -> Result<Self> {
+        Self::load_from(Path::new(POLICY_FILE))
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read policy {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse policy {}", path.display()))
+    }
+
+    fn is_restricted(&self) -> bool {
+        !self.allowed_hosts.is_empty() || !self.allowed_prefixes.is_empty()
+    }
+
+    /// Refuse `url` (repo, release source or package index) unless its host
+    /// or URL prefix is allowed
+    pub fn check(&self, tool_name: &str, url: &str) -> Result<()> {
+        if !self.is_restricted() {
+            return Ok(());
+        }
+
+        let host = host_of(url).to_ascii_lowercase();
+        let host_allowed = !host.is_empty()
+            && self.allowed_hosts.iter().any(|allowed| {
+                let allowed = allowed.to_ascii_lowercase();
+                match allowed.strip_prefix("*.") {
+                    Some(domain) => host
+                        .strip_suffix(domain)
+                        .is_some_and(|sub| sub.ends_with('.')),
+                    None => host == allowed,
+                }
+            });
+        let prefix_allowed = self
+            .allowed_prefixes
+            .iter()
+            .any(|prefix| prefix_matches(prefix, url));
+
+        if host_allowed || prefix_allowed {
+            Ok(())
+        } else {
+            Err(TuxBoxError::PolicyError(format!(
+                "'{}' comes from {}, which is not an allowed source (see {})",
+                tool_name, url, POLICY_FILE
+            ))
+            .into())
+        }
+    }
+}
+
+/// `url` is `prefix` or below it: same scheme, host and port, and the
+/// prefix's path segments lead the URL's after `.`/`..` are resolved
+fn prefix_matches(prefix: &str, url: &str) -> bool {
+    let (Some(prefix), Some(url)) = (parse_source(prefix), parse_source(url)) else {
+        return false;
+    };
+    let segments = |u: &reqwest::Url| -> Vec<String> {
+        u.path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    let same_host = match (prefix.host_str(), url.host_str()) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    };
+    prefix.scheme() == url.scheme()
+        && same_host
+        && prefix.port_or_known_default() == url.port_or_known_default()
+        && segments(&url).starts_with(&segments(&prefix))
+}
+
+/// Parse a source URL; scp-like `git@host:path` is read as `ssh://git@host/path`
+fn parse_source(url: &str) -> Option<reqwest::Url> {
+    if !url.contains("://")
+        && let Some((authority, path)) = url.split_once(':')
+        && !authority.contains('/')
+    {
+        return reqwest::Url::parse(&format!("ssh://{}/{}", authority, path)).ok();
+    }
+    reqwest::Url::parse(url).ok()
+}
+
+/// What the user approved for a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovedTool {
+    pub registry: String,
+    pub repo: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub setup: Option<String>,
+    /// The whole tool entry (TOML, keys sorted): container flags, sandbox,
+    /// release source, package index, ... Any change asks again.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub definition: String,
+}
+
+impl ApprovedTool {
+    fn for_tool(tool_config: &ToolConfig, registry: &str) -> Result<Self> {
+        let commands = tool_config.commands.as_ref();
+        Ok(ApprovedTool {
+            registry: registry.to_string(),
+            repo: tool_config.repo.clone(),
+            run: commands.map(|c| c.run.clone()),
+            setup: commands.and_then(|c| c.setup.clone()),
+            definition: definition(tool_config)?,
+        })
+    }
+}
+
+/// Canonical TOML of a tool entry; going through `toml::Value` sorts the keys,
+/// so maps (sha256 pins, container env) compare equal across runs
+fn definition(tool_config: &ToolConfig) -> Result<String> {
+    let value = toml::Value::try_from(tool_config)
+        .with_context(|| format!("Cannot serialize the definition of '{}'", tool_config.name))?;
+    Ok(toml::to_string_pretty(&value)?)
+}
+
+/// `~/.tuxbox/trust.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    /// Trusted registries: name → URL at the time it was trusted
+    #[serde(default)]
+    pub registries: BTreeMap<String, String>,
+    /// Approved tools, keyed by name
+    #[serde(default)]
+    pub tools: BTreeMap<String, ApprovedTool>,
+}
+
+fn trust_file() -> Result<PathBuf> {
+    Ok(config::tuxbox_home()?.join("trust.toml"))
+}
+
+impl TrustStore {
+    pub fn load() -> Result<Self> {
+        let path = trust_file()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = trust_file()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// A registry is trusted under its name and the URL it had when trusted
+    fn is_registry_trusted(&self, registry: &RegistryConfig) -> bool {
+        self.registries.get(&registry.name) == Some(&registry.url)
+    }
+}
+
+/// Make sure the user accepted what `tool_config` (from `registry_name`) will
+/// execute, prompting on first use and whenever the repo or commands changed
+pub fn confirm_tool(tool_config: &ToolConfig, registry_name: &str) -> Result<()> {
+    let mut store = TrustStore::load()?;
+
+    let registry = config::load_config()?
+        .registries
+        .into_iter()
+        .find(|r| r.name == registry_name);
+    if registry.is_some_and(|r| store.is_registry_trusted(&r)) {
+        return Ok(());
+    }
+
+    let current = ApprovedTool::for_tool(tool_config, registry_name)?;
+    let previous = store.tools.get(&tool_config.name);
+    if previous == Some(&current) {
+        return Ok(());
+    }
+
+    match previous {
+        Some(previous) => {
+            println!(
+                "  {} '{}' changed since you approved it:",
+                "⚠".yellow(),
+                tool_config.name.bold()
+            );
+            print_change(
+                "Registry",
+                Some(&previous.registry),
+                Some(&current.registry),
+            );
+            print_change("Repo", Some(&previous.repo), Some(&current.repo));
+            print_change("Run", previous.run.as_ref(), current.run.as_ref());
+            print_change("Setup", previous.setup.as_ref(), current.setup.as_ref());
+            print_definition_diff(&previous.definition, &current.definition);
+        }
+        None => {
+            println!(
+                "  {} First run of '{}' from registry '{}':",
+                "→".cyan(),
+                tool_config.name.bold(),
+                registry_name
+            );
+            println!("      Repo:  {}", current.repo);
+            if let Some(tool_type) = &tool_config.tool_type {
+                println!("      Type:  {}", tool_type);
+            }
+            if let Some(run) = &current.run {
+                println!("      Run:   {}", run);
+            }
+            if let Some(setup) = &current.setup {
+                println!("      Setup: {}", setup);
+            }
+            println!("      Definition:");
+            for line in current.definition.lines() {
+                println!("        {}", line.dimmed());
+            }
+        }
+    }
+
+    if !ask("Trust and run it?")? {
+        return Err(TuxBoxError::PolicyError(format!(
+            "'{}' was not approved. Run it interactively to review it, or trust its registry \
+             with `tbox trust registry {}`",
+            tool_config.name, registry_name
+        ))
+        .into());
+    }
+
+    store.tools.insert(tool_config.name.clone(), current);
+    store.save()
+}
+
+fn print_change(label: &str, old: Option<&String>, new: Option<&String>) {
+    if old == new {
+        return;
+    }
+    let show = |value: Option<&String>| value.cloned().unwrap_or_else(|| "(none)".to_string());
+    println!(
+        "      {}: {} {} {}",
+        label,
+        show(old).red(),
+        "→".cyan(),
+        show(new).green()
+    );
+}
+
+/// Lines of the tool entry that were removed (-) or added (+) since the approval
+fn print_definition_diff(old: &str, new: &str) {
+    if old == new {
+        return;
+    }
+    if old.is_empty() {
+        println!("      Definition (not recorded in the earlier approval):");
+    } else {
+        println!("      Definition:");
+    }
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    for line in old_lines.iter().filter(|l| !new_lines.contains(l)) {
+        println!("        {}", format!("- {}", line).red());
+    }
+    for line in new_lines.iter().filter(|l| !old_lines.contains(l)) {
+        println!("        {}", format!("+ {}", line).green());
+    }
+}
+
+/// y/N prompt on the terminal; false without one
+fn ask(question: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    print!("  {} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// `tbox trust registry <name>`: run its tools without prompting
+pub fn trust_registry(name: &str) -> Result<()> {
+    let registry = config::load_config()?
+        .registries
+        .into_iter()
+        .find(|r| r.name == name)
+        .ok_or_else(|| TuxBoxError::ConfigError(format!("Registry '{}' not found", name)))?;
+
+    let mut store = TrustStore::load()?;
+    store
+        .registries
+        .insert(registry.name.clone(), registry.url.clone());
+    store.save()?;
+    println!(
+        "  {} Trusted registry '{}' ({})",
+        "✓".green(),
+        registry.name,
+        registry.url
+    );
+    Ok(())
+}
+
+/// `tbox trust revoke <name>`: forget a trusted registry or an approved tool
+pub fn revoke(name: &str) -> Result<()> {
+    let mut store = TrustStore::load()?;
+    let registry = store.registries.remove(name).is_some();
+    let tool = store.tools.remove(name).is_some();
+    if !registry && !tool {
+        return Err(TuxBoxError::ConfigError(format!(
+            "'{}' is neither a trusted registry nor an approved tool",
+            name
+        ))
+        .into());
+    }
+    store.save()?;
+    if registry {
+        println!("  {} Registry '{}' is no longer trusted", "✓".green(), name);
+    }
+    if tool {
+        println!(
+            "  {} Approval of '{}' revoked, its next run will ask again",
+            "✓".green(),
+            name
+        );
+    }
+    Ok(())
+}
+
+/// `tbox trust list`
+pub fn list() -> Result<()> {
+    let store = TrustStore::load()?;
+
+    println!("{} Trusted registries:", "→".cyan());
+    if store.registries.is_empty() {
+        println!("  (none)");
+    }
+    for (name, url) in &store.registries {
+        println!("  {} {} ({})", "✓".green(), name.bold(), url);
+    }
+
+    println!("{} Approved tools:", "→".cyan());
+    if store.tools.is_empty() {
+        println!("  (none)");
+    }
+    for (name, tool) in &store.tools {
+        println!(
+            "  {} {} from {} ({})",
+            "✓".green(),
+            name.bold(),
+            tool.registry,
+            tool.repo
+        );
+    }
+
+    let policy = Policy::load()?;
+    if policy.is_restricted() {
+        println!("{} Admin policy ({}):", "→".cyan(), POLICY_FILE);
+        for host in &policy.allowed_hosts {
+            println!("  host:   {}", host);
+        }
+        for prefix in &policy.allowed_prefixes {
+            println!("  prefix: {}", prefix);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.toml");
+        std::fs::write(
+            &path,
+            r#"
+allowed_hosts = ["github.com", "*.corp.example"]
+allowed_prefixes = ["https://gitlab.com/acme/", "git@git.example:team/"]
+"#,
+        )
+        .unwrap();
+        let policy = Policy::load_from(&path).unwrap();
+
+        assert!(policy.check("a", "https://github.com/owner/a").is_ok());
+        assert!(policy.check("b", "git@git.corp.example:team/b.git").is_ok());
+        assert!(policy.check("c", "https://gitlab.com/acme/c").is_ok());
+        assert!(policy.check("d", "https://gitlab.com/other/d").is_err());
+        assert!(policy.check("e", "https://evilcorp.example/x/e").is_err());
+        assert!(policy.check("f", "/tmp/f").is_err());
+        assert!(
+            policy
+                .check("g", "https://mirror.corp.example/simple")
+                .is_ok()
+        );
+        assert!(
+            policy
+                .check("h", "https://pypi.evil.example/simple")
+                .is_err()
+        );
+
+        // Prefixes match whole path segments, after `..` is resolved
+        assert!(policy.check("i", "https://gitlab.com/acme-evil/i").is_err());
+        assert!(
+            policy
+                .check("j", "https://gitlab.com/acme/../attacker/j")
+                .is_err()
+        );
+        assert!(
+            policy
+                .check("k", "https://gitlab.com/acme/%2e%2e/attacker/k")
+                .is_err()
+        );
+        assert!(policy.check("l", "HTTPS://GitLab.com/acme/l").is_ok());
+        assert!(policy.check("m", "http://gitlab.com/acme/m").is_err());
+        assert!(policy.check("n", "https://GIT.Corp.Example/n").is_ok());
+        assert!(policy.check("o", "git@git.example:team/o.git").is_ok());
+        assert!(policy.check("p", "git@git.example:team/../p.git").is_err());
+        assert!(
+            policy
+                .check("q", "ssh://git@git.example/team/q.git")
+                .is_ok()
+        );
+
+        let open = Policy::load_from(&dir.path().join("missing.toml")).unwrap();
+        assert!(open.check("f", "/tmp/f").is_ok());
+    }
+
+    #[test]
+    fn test_approval_covers_whole_definition() {
+        let mut tool = ToolConfig {
+            name: "scan".to_string(),
+            repo: "https://github.com/acme/scan".to_string(),
+            sha256: Some(
+                (0..8)
+                    .map(|i| (format!("platform-{}", i), format!("{:064}", i)))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let approved = ApprovedTool::for_tool(&tool, "reg").unwrap();
+        assert_eq!(approved, ApprovedTool::for_tool(&tool, "reg").unwrap());
+
+        tool.sandbox = Some(crate::sandbox::SandboxConfig {
+            network: true,
+            ..Default::default()
+        });
+        assert_ne!(approved, ApprovedTool::for_tool(&tool, "reg").unwrap());
+    }
+}
@@ -0,0 +1,165 @@
+//! `tbox bundle export` → `tbox bundle import` between two TuxBox homes
+
+use assert_cmd::Command;
+use std::path::Path;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.email=dev@example.com", "-c", "user.name=dev"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?}", args);
+}
+
+fn tbox(home: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("tbox").unwrap();
+    cmd.env("HOME", home)
+        .env("NO_COLOR", "1")
+        .env_remove("TUXBOX_SANDBOX");
+    cmd
+}
+
+/// A tool repo, a registry listing it, and an exporting home with both
+/// synced and installed. Returns the exporting home.
+fn setup(root: &Path) -> std::path::PathBuf {
+    let tool = root.join("tool");
+    std::fs::create_dir_all(&tool).unwrap();
+    std::fs::write(tool.join("hello.sh"), "echo hello\n").unwrap();
+    git(&tool, &["init", "-q"]);
+    git(&tool, &["add", "."]);
+    git(&tool, &["commit", "-qm", "tool"]);
+
+    let registry = root.join("registry");
+    std::fs::create_dir_all(&registry).unwrap();
+    std::fs::write(
+        registry.join("tools.toml"),
+        format!(
+            "[tools.hello]\nname = \"hello\"\nrepo = \"{}\"\ntype = \"bash\"\n\
+             [tools.hello.commands]\nrun = \"./hello.sh\"\n",
+            tool.display()
+        ),
+    )
+    .unwrap();
+    git(&registry, &["init", "-q"]);
+    git(&registry, &["add", "."]);
+    git(&registry, &["commit", "-qm", "registry"]);
+
+    let home = root.join("online");
+    std::fs::create_dir_all(&home).unwrap();
+    tbox(&home)
+        .args(["init", &registry.to_string_lossy()])
+        .assert()
+        .success();
+    tbox(&home).args(["registry", "sync"]).assert().success();
+    git(
+        root,
+        &[
+            "clone",
+            "-q",
+            &tool.to_string_lossy(),
+            &home.join(".tuxbox/tools/hello").to_string_lossy(),
+        ],
+    );
+    home
+}
+
+fn head(repo: &Path) -> String {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(repo)
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn test_export_import_roundtrip() {
+    let root = tempfile::tempdir().unwrap();
+    let online = setup(root.path());
+    let archive = root.path().join("bundle.tar.gz");
+    tbox(&online)
+        .args(["bundle", "export", "-o", &archive.to_string_lossy()])
+        .assert()
+        .success();
+
+    let offline = root.path().join("offline");
+    std::fs::create_dir_all(&offline).unwrap();
+    tbox(&offline)
+        .args(["bundle", "import", &archive.to_string_lossy()])
+        .assert()
+        .success();
+
+    let tool = offline.join(".tuxbox/tools/hello");
+    assert!(tool.join("hello.sh").is_file());
+    assert_eq!(head(&tool), head(&online.join(".tuxbox/tools/hello")));
+    let config = std::fs::read_to_string(offline.join(".tuxbox/config.toml")).unwrap();
+    assert!(config.contains("file://"), "{config}");
+    assert!(!config.contains("[registries.signing]"), "{config}");
+}
+
+#[test]
+fn test_import_keeps_registry_signing() {
+    let root = tempfile::tempdir().unwrap();
+    let key = root.path().join("key");
+    let keygen = std::process::Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+        .arg(&key)
+        .status();
+    if !keygen.is_ok_and(|s| s.success()) {
+        eprintln!("ssh-keygen not available, skipping");
+        return;
+    }
+    let online = setup(root.path());
+
+    // Sign the registry's next revision and require signatures from now on
+    let public = std::fs::read_to_string(key.with_extension("pub")).unwrap();
+    let signers = root.path().join("allowed_signers");
+    std::fs::write(&signers, format!("dev@example.com {}", public)).unwrap();
+    let registry = root.path().join("registry");
+    std::fs::write(registry.join("README"), "signed\n").unwrap();
+    git(&registry, &["add", "."]);
+    git(
+        &registry,
+        &[
+            "-c",
+            "gpg.format=ssh",
+            "-c",
+            &format!("user.signingkey={}", key.display()),
+            "commit",
+            "-S",
+            "-qm",
+            "signed",
+        ],
+    );
+    tbox(&online)
+        .args(["registry", "set-signing", "registry"])
+        .args(["--allowed-signers", &signers.to_string_lossy()])
+        .assert()
+        .success();
+    tbox(&online).args(["registry", "sync"]).assert().success();
+
+    let archive = root.path().join("bundle.tar.gz");
+    tbox(&online)
+        .args(["bundle", "export", "-o", &archive.to_string_lossy()])
+        .assert()
+        .success();
+    // The offline host only has what is in the archive
+    std::fs::remove_file(&signers).unwrap();
+
+    let offline = root.path().join("offline");
+    std::fs::create_dir_all(&offline).unwrap();
+    tbox(&offline)
+        .args(["bundle", "import", &archive.to_string_lossy()])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Registry commit signed by"));
+
+    let config = std::fs::read_to_string(offline.join(".tuxbox/config.toml")).unwrap();
+    assert!(config.contains("[registries.signing]"), "{config}");
+    assert!(
+        config.contains(".tuxbox/mirrors/registries/registry.allowed_signers"),
+        "{config}"
+    );
+}